@@ -101,6 +101,17 @@ impl<T: BufferDataType> Buffer<T> {
         self.attribute_count
     }
 
+    pub fn read(&self) -> Vec<T> {
+        let mut bytes = vec![0u8; self.attribute_count as usize * std::mem::size_of::<T>()];
+        self.bind();
+        unsafe {
+            self.context
+                .get_buffer_sub_data(crate::context::ARRAY_BUFFER, 0, &mut bytes);
+            self.context.bind_buffer(crate::context::ARRAY_BUFFER, None);
+        }
+        from_byte_slice(&bytes).to_vec()
+    }
+
     pub fn bind(&self) {
         unsafe {
             self.context
@@ -112,6 +112,22 @@ impl<T: ElementBufferDataType> ElementBuffer<T> {
         self.count / 3
     }
 
+    ///
+    /// Returns the indices in the element buffer.
+    /// This requires the indices to be transferred from the GPU, so it should be avoided in performance critical code.
+    ///
+    pub fn read(&self) -> Vec<T> {
+        let mut bytes = vec![0u8; self.count as usize * std::mem::size_of::<T>()];
+        self.bind();
+        unsafe {
+            self.context
+                .get_buffer_sub_data(crate::context::ELEMENT_ARRAY_BUFFER, 0, &mut bytes);
+            self.context
+                .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
+        }
+        from_byte_slice(&bytes).to_vec()
+    }
+
     pub(crate) fn bind(&self) {
         unsafe {
             self.context
@@ -60,6 +60,14 @@ impl<T: BufferDataType> VertexBuffer<T> {
         self.buffer.attribute_count()
     }
 
+    ///
+    /// Returns the data in the vertex buffer.
+    /// This requires the data to be transferred from the GPU, so it should be avoided in performance critical code.
+    ///
+    pub fn read(&self) -> Vec<T> {
+        self.buffer.read()
+    }
+
     pub(in crate::core) fn bind(&self) {
         self.buffer.bind();
     }
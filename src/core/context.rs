@@ -1,4 +1,5 @@
 use super::*;
+use std::any::{Any, TypeId};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::RwLock;
@@ -6,6 +7,11 @@ use std::sync::RwLock;
 #[doc(hidden)]
 pub use crate::context::HasContext;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) type Resource = Box<dyn Any + Send + Sync>;
+#[cfg(target_arch = "wasm32")]
+pub(crate) type Resource = Box<dyn Any>;
+
 ///
 /// Contains the low-level OpenGL/WebGL graphics context as well as other "global" variables.
 /// Implements Deref with the low-level graphics context as target, so you can call low-level functionality
@@ -17,6 +23,8 @@ pub struct Context {
     pub(super) vao: crate::context::VertexArray,
     /// A cache of programs to avoid recompiling a [Program] every frame.
     pub programs: Arc<RwLock<HashMap<Vec<u8>, Program>>>,
+    /// A cache of resources, for example lookup tables, which are expensive to create and therefore shared by everything using this context.
+    resources: Arc<RwLock<HashMap<TypeId, Resource>>>,
 }
 
 impl Context {
@@ -44,11 +52,33 @@ impl Context {
                 context,
                 vao,
                 programs: Arc::new(RwLock::new(HashMap::new())),
+                resources: Arc::new(RwLock::new(HashMap::new())),
             }
         };
         Ok(c)
     }
 
+    ///
+    /// Removes the resource of type `T` from the cache of resources and returns it, or returns `None` if there is no resource of that type.
+    ///
+    pub(crate) fn take_resource<T: Any>(&self) -> Option<T> {
+        self.resources
+            .write()
+            .unwrap()
+            .remove(&TypeId::of::<T>())
+            .and_then(|resource| resource.downcast().ok())
+            .map(|resource| *resource)
+    }
+
+    ///
+    /// Stores the given resource in the cache of resources, replacing any resource of the same type.
+    /// Use [Context::take_resource] to get it back.
+    ///
+    pub(crate) fn set_resource(&self, resource: Resource) {
+        let type_id = (*resource).type_id();
+        self.resources.write().unwrap().insert(type_id, resource);
+    }
+
     ///
     /// Set the scissor test for this context (see [ScissorBox]).
     ///
@@ -82,6 +112,22 @@ impl Context {
         }
     }
 
+    ///
+    /// Set the polygon offset for this context which offsets the depth of each fragment by `factor * slope + units * r`,
+    /// where `slope` is the depth slope of the triangle and `r` is the smallest resolvable depth difference.
+    /// The polygon offset is disabled if both `factor` and `units` are zero.
+    ///
+    pub fn set_polygon_offset(&self, factor: f32, units: f32) {
+        unsafe {
+            if factor == 0.0 && units == 0.0 {
+                self.disable(crate::context::POLYGON_OFFSET_FILL);
+            } else {
+                self.enable(crate::context::POLYGON_OFFSET_FILL);
+                self.polygon_offset(factor, units);
+            }
+        }
+    }
+
     ///
     /// Set the face culling for this context (see [Cull]).
     ///
@@ -29,6 +29,8 @@ pub enum RendererError {
     InvalidBufferLength(String, usize, usize),
     #[error("the material {0} is required by the geometry {1} but could not be found")]
     MissingMaterial(String, String),
+    #[error("failed to parse IES profile: {0}")]
+    InvalidIesProfile(String),
     #[cfg(feature = "text")]
     #[error("Failed to find font with index {0} in the given font collection")]
     MissingFont(u32),
@@ -103,11 +105,18 @@ macro_rules! impl_render_target_extensions_body {
                 let geometry_pass_camera = GeometryPassCamera(&viewer);
                 let viewport = geometry_pass_camera.viewport();
                 deferred_objects.sort_by(|a, b| cmp_render_order(&geometry_pass_camera, a, b));
+                // The fourth layer is only needed if any of the materials writes to it
+                let gbuffer_layers: &[u32] =
+                    if deferred_objects.iter().any(|o| o.uses_extensions_layer()) {
+                        &[0, 1, 2, 3]
+                    } else {
+                        &[0, 1, 2]
+                    };
                 let mut geometry_pass_texture = Texture2DArray::new_empty::<[u8; 4]>(
                     &self.context,
                     viewport.width,
                     viewport.height,
-                    3,
+                    gbuffer_layers.len() as u32,
                     Interpolation::Nearest,
                     Interpolation::Nearest,
                     None,
@@ -121,9 +130,8 @@ macro_rules! impl_render_target_extensions_body {
                     Wrapping::ClampToEdge,
                     Wrapping::ClampToEdge,
                 );
-                let gbuffer_layers = [0, 1, 2];
                 RenderTarget::new(
-                    geometry_pass_texture.as_color_target(&gbuffer_layers, None),
+                    geometry_pass_texture.as_color_target(gbuffer_layers, None),
                     geometry_pass_depth_texture.as_depth_target(),
                 )
                 .clear(ClearState::default())
@@ -143,7 +151,7 @@ macro_rules! impl_render_target_extensions_body {
                     lights,
                     Some(ColorTexture::Array {
                         texture: &geometry_pass_texture,
-                        layers: &gbuffer_layers,
+                        layers: gbuffer_layers,
                     }),
                     Some(DepthTexture::Single(&geometry_pass_depth_texture)),
                 );
@@ -161,6 +169,117 @@ macro_rules! impl_render_target_extensions_body {
             self
         }
 
+        ///
+        /// Render the objects using the given viewer and lights into this render target like [Self::render],
+        /// except that transparent objects are rendered using weighted blended order-independent transparency.
+        /// See [Self::render_partially_with_order_independent_transparency] for details.
+        ///
+        pub fn render_with_order_independent_transparency(
+            &self,
+            viewer: impl Viewer,
+            objects: impl IntoIterator<Item = impl Object>,
+            lights: &[&dyn Light],
+        ) -> &Self {
+            self.render_partially_with_order_independent_transparency(
+                self.scissor_box(),
+                viewer,
+                objects,
+                lights,
+            )
+        }
+
+        ///
+        /// Render the objects using the given viewer and lights into the part of this render target defined by the scissor box like [Self::render_partially],
+        /// except that transparent objects are rendered using weighted blended order-independent transparency.
+        ///
+        /// The transparent objects are accumulated into a weighted sum of their colors and the product of their transparencies, which is then composited on top of the opaque objects.
+        /// Therefore the result does not depend on the order of the transparent objects and intersecting transparent objects are rendered correctly,
+        /// but the colors are an approximation where the surfaces closest to the viewer are weighted higher.
+        /// Transparent objects that do not support this, see [Object::render_order_independent], are rendered in the order given by [cmp_render_order] on top of the composited result,
+        /// which means that they are not blended in the correct order with the transparent objects rendered using order-independent transparency.
+        /// The depth of the opaque objects is rendered an extra time using their [depth material](Object::depth_material) to hide the transparent objects behind them.
+        /// The textures needed for this are stored in the [Context] and reused as long as the size of the viewport does not change.
+        ///
+        pub fn render_partially_with_order_independent_transparency(
+            &self,
+            scissor_box: ScissorBox,
+            viewer: impl Viewer,
+            objects: impl IntoIterator<Item = impl Object>,
+            lights: &[&dyn Light],
+        ) -> &Self {
+            let frustum = Frustum::new(viewer.projection() * viewer.view());
+            let (transparent_objects, opaque_objects): (Vec<_>, Vec<_>) = objects
+                .into_iter()
+                .filter(|o| frustum.contains(o.aabb()))
+                .partition(|o| o.material_type() == MaterialType::Transparent);
+            self.render_partially(scissor_box, &viewer, &opaque_objects, lights);
+            if transparent_objects.is_empty() {
+                return self;
+            }
+
+            // Accumulation pass
+            let accumulation_camera = GeometryPassCamera(&viewer);
+            let viewport = accumulation_camera.viewport();
+            let mut targets =
+                order_independent_transparency::OrderIndependentTransparencyTargets::new_or_reuse(
+                    &self.context,
+                    viewport.width,
+                    viewport.height,
+                );
+            let accumulation_layers = [0, 1];
+            let mut unsupported_objects = Vec::new();
+            RenderTarget::new(
+                targets
+                    .accumulation_texture
+                    .as_color_target(&accumulation_layers, None),
+                targets.depth_texture.as_depth_target(),
+            )
+            .clear(ClearState::depth(1.0))
+            .write::<RendererError>(|| {
+                // Only the depth of the opaque objects is needed to hide the transparent objects behind them
+                for object in opaque_objects.iter() {
+                    let depth_material = DepthMaterial {
+                        render_states: RenderStates {
+                            write_mask: WriteMask::DEPTH,
+                            ..Default::default()
+                        },
+                        ..object.depth_material().unwrap_or_default()
+                    };
+                    object.render_with_material(&depth_material, &accumulation_camera, &[]);
+                }
+                Ok(())
+            })
+            .unwrap()
+            .clear(ClearState::color(0.0, 0.0, 0.0, 1.0))
+            .write::<RendererError>(|| {
+                for object in transparent_objects.iter() {
+                    if !object.render_order_independent(&self.context, &accumulation_camera, lights)
+                    {
+                        unsupported_objects.push(object);
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+
+            // Composite pass
+            self.apply_screen_effect_partially(
+                scissor_box,
+                &order_independent_transparency::OrderIndependentTransparencyEffect {},
+                &viewer,
+                &[],
+                Some(ColorTexture::Array {
+                    texture: &targets.accumulation_texture,
+                    layers: &accumulation_layers,
+                }),
+                None,
+            );
+            self.context.set_resource(Box::new(targets));
+
+            self.render_partially(scissor_box, &viewer, unsupported_objects, lights);
+            self
+        }
+
         ///
         /// Render the geometries with the given [Material] using the given viewer and lights into this render target.
         /// Use an empty array for the `lights` argument, if the material does not require lights to be rendered.
@@ -430,6 +549,54 @@ pub fn render_with_material(
     geometry.draw(&viewer, program, material.render_states());
 }
 
+///
+/// Render the given [Geometry] with the given [Material] into the accumulation and revealage targets used by [RenderTarget::render_with_order_independent_transparency].
+/// Must be called from [Object::render_order_independent].
+/// The material must write its color to an output called `outColor` at location zero, which all materials in this crate do,
+/// and the blending and depth write of the [render states](Material::render_states) are replaced.
+/// Use an empty array for the `lights` argument, if the material does not require lights to be rendered.
+///
+pub fn render_order_independent(
+    context: &Context,
+    viewer: impl Viewer,
+    geometry: impl Geometry,
+    material: impl Material,
+    lights: &[&dyn Light],
+) {
+    let id = combine_ids(
+        geometry.id(),
+        material.id(),
+        lights
+            .iter()
+            .map(|l| l.id())
+            .chain(std::iter::once(LightId::OrderIndependentTransparency)),
+    );
+
+    let mut programs = context.programs.write().unwrap();
+    let program = programs.entry(id).or_insert_with(|| {
+        match Program::from_source(
+            context,
+            &geometry.vertex_shader_source(),
+            &order_independent_transparency::accumulation_fragment_shader_source(
+                &material.fragment_shader_source(lights),
+            ),
+        ) {
+            Ok(program) => program,
+            Err(err) => panic!("{}", err.to_string()),
+        }
+    });
+    material.use_uniforms(program, &viewer, lights);
+    geometry.draw(
+        &viewer,
+        program,
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            blend: order_independent_transparency::ACCUMULATION_BLEND,
+            ..material.render_states()
+        },
+    );
+}
+
 ///
 /// Render the given [Geometry] with the given [Effect].
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
@@ -62,6 +62,8 @@ pub use water::*;
 
 pub(crate) mod lighting_pass;
 
+pub(crate) mod order_independent_transparency;
+
 use crate::renderer::*;
 use std::ops::Deref;
 
@@ -10,6 +10,9 @@ impl Effect for LightingPassEffect {
         depth_texture: Option<DepthTexture>,
     ) -> String {
         let mut fragment_shader = lights_shader_source(lights);
+        if has_extensions_layer(color_texture.unwrap()) {
+            fragment_shader.push_str("#define USE_EXTENSIONS\n");
+        }
         fragment_shader.push_str(&color_texture.unwrap().fragment_shader_source());
         fragment_shader.push_str(&depth_texture.unwrap().fragment_shader_source());
         fragment_shader.push_str(ToneMapping::fragment_shader_source());
@@ -23,7 +26,11 @@ impl Effect for LightingPassEffect {
         color_texture: Option<ColorTexture>,
         depth_texture: Option<DepthTexture>,
     ) -> EffectMaterialId {
-        EffectMaterialId::LightingPassEffect(color_texture.unwrap(), depth_texture.unwrap())
+        EffectMaterialId::LightingPassEffect(
+            color_texture.unwrap(),
+            depth_texture.unwrap(),
+            has_extensions_layer(color_texture.unwrap()),
+        )
     }
 
     fn use_uniforms(
@@ -65,6 +72,14 @@ impl Effect for LightingPassEffect {
     }
 }
 
+///
+/// Whether the geometry pass texture has the fourth layer with the specular, clearcoat and sheen parameters,
+/// which is optional so that a geometry pass texture with only three layers still works.
+///
+fn has_extensions_layer(color_texture: ColorTexture) -> bool {
+    matches!(color_texture, ColorTexture::Array { layers, .. } if layers.len() > 3)
+}
+
 ///
 /// Used for debug purposes - only internal.
 ///
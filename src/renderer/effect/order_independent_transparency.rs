@@ -0,0 +1,119 @@
+use crate::renderer::*;
+
+///
+/// The blending used when rendering into the accumulation and revealage targets.
+/// The color channels add up the output, while the alpha channel multiplies the target with one minus the output alpha,
+/// so the same blending works for both targets when they are cleared to `(0, 0, 0, 1)`.
+///
+pub const ACCUMULATION_BLEND: Blend = Blend::Enabled {
+    source_rgb_multiplier: BlendMultiplierType::One,
+    source_alpha_multiplier: BlendMultiplierType::Zero,
+    destination_rgb_multiplier: BlendMultiplierType::One,
+    destination_alpha_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+    rgb_equation: BlendEquationType::Add,
+    alpha_equation: BlendEquationType::Add,
+};
+
+///
+/// Wraps the given material fragment shader source so that the color written to `outColor` is weighted
+/// and written into the accumulation target while the weight is written into the second target.
+///
+pub fn accumulation_fragment_shader_source(material_source: &str) -> String {
+    format!(
+        "#define main material_main\n{}\n{}",
+        material_source,
+        include_str!("shaders/order_independent_accumulation.frag")
+    )
+}
+
+///
+/// The accumulation and revealage targets and the depth texture used by [RenderTarget::render_with_order_independent_transparency].
+/// They are stored in the [Context] after each use and reused as long as the size does not change.
+///
+pub struct OrderIndependentTransparencyTargets {
+    /// Two layers, the accumulation target and the revealage target.
+    pub accumulation_texture: Texture2DArray,
+    /// Contains the depth of the opaque objects.
+    pub depth_texture: DepthTexture2D,
+}
+
+impl OrderIndependentTransparencyTargets {
+    ///
+    /// Returns the targets stored in the context if they have the given size, otherwise new targets with the given size.
+    ///
+    pub fn new_or_reuse(context: &Context, width: u32, height: u32) -> Self {
+        context
+            .take_resource::<Self>()
+            .filter(|targets| {
+                targets.depth_texture.width() == width && targets.depth_texture.height() == height
+            })
+            .unwrap_or_else(|| Self {
+                accumulation_texture: Texture2DArray::new_empty::<[f16; 4]>(
+                    context,
+                    width,
+                    height,
+                    2,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                depth_texture: DepthTexture2D::new::<f32>(
+                    context,
+                    width,
+                    height,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+            })
+    }
+}
+
+///
+/// Composites the accumulated transparent objects on top of the opaque objects.
+///
+pub struct OrderIndependentTransparencyEffect {}
+
+impl Effect for OrderIndependentTransparencyEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}",
+            color_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/order_independent_composite.frag")
+        )
+    }
+
+    fn id(
+        &self,
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> EffectMaterialId {
+        EffectMaterialId::OrderIndependentTransparencyEffect(color_texture.unwrap())
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _viewer: &dyn Viewer,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture.unwrap().use_uniforms(program);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            blend: Blend::TRANSPARENCY,
+            ..Default::default()
+        }
+    }
+}
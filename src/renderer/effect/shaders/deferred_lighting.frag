@@ -33,7 +33,18 @@ void main()
     vec3 normal = normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z: -z));
     float roughness_factor = n.w;
     float occlusion = float(int(floor(n.z * 255.0)) & 127) / 127.0;
-    vec3 total_emissive = sample_layer(uvs, 2).rgb;
+    vec4 e = sample_layer(uvs, 2);
+    vec3 total_emissive = e.rgb;
+
+#ifdef USE_EXTENSIONS
+    vec4 x = sample_layer(uvs, 3);
+    material_dielectric_f0 = vec3(x.r * x.r);
+    material_clearcoat = e.a;
+    material_clearcoat_roughness = x.g;
+    material_clearcoat_normal = normal;
+    material_sheen_color = vec3(x.b);
+    material_sheen_roughness = x.a;
+#endif
 
     if(debug_type == 0) // Position
     {
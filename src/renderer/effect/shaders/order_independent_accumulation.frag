@@ -0,0 +1,18 @@
+#undef main
+
+layout (location = 1) out vec4 outWeight;
+
+void main()
+{
+    material_main();
+
+    // The weight function from 'Weighted Blended Order-Independent Transparency' by McGuire and Bavoil (2013),
+    // which makes the surfaces closest to the camera dominate the result.
+    // 1 / gl_FragCoord.w is the view space depth for a perspective projection.
+    float alpha = clamp(outColor.a, 0.0, 1.0);
+    float z = 1.0 / gl_FragCoord.w;
+    float weight = alpha * clamp(10.0 / (0.00001 + pow(z / 5.0, 2.0) + pow(z / 200.0, 6.0)), 0.01, 3000.0);
+
+    outColor = vec4(outColor.rgb * alpha * weight, alpha);
+    outWeight = vec4(alpha * weight, 0.0, 0.0, alpha);
+}
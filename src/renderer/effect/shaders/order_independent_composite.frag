@@ -0,0 +1,15 @@
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 accumulation = sample_layer(uvs, 0);
+    float revealage = accumulation.a;
+    if (revealage > 0.9999) {
+        discard;
+    }
+    float weight = sample_layer(uvs, 1).r;
+    outColor = vec4(accumulation.rgb / max(weight, 0.00001), 1.0 - revealage);
+}
@@ -80,6 +80,7 @@ pub use circle::*;
 
 use crate::core::*;
 use crate::renderer::*;
+use std::sync::OnceLock;
 
 pub use three_d_asset::{
     Geometry as CpuGeometry, Indices, KeyFrameAnimation, KeyFrames, PointCloud, Positions,
@@ -97,7 +98,9 @@ pub use three_d_asset::{
 /// - tangent: `out vec3 tang;`
 /// - bitangent: `out vec3 bitang;`
 /// - uv coordinates: `out vec2 uvs;` (must be flipped in v compared to standard uv coordinates, ie. do `uvs = vec2(uvs.x, 1.0 - uvs.y);` in the vertex shader or do the flip before constructing the uv coordinates vertex buffer)
+/// - second set of uv coordinates, for example used for lightmaps: `out vec2 uvs2;` (must be flipped in v in the same way as the uv coordinates)
 /// - color: `out vec4 col;`
+/// - barycentric coordinates of the triangle, for example used for wireframes: `out vec3 barycentric;` (one of `(1, 0, 0)`, `(0, 1, 0)` and `(0, 0, 1)` at each of the three vertices of a triangle, or `(1, 1, 1)` at all vertices if they are not available)
 ///
 /// In addition, for the geometry to be pickable using the [pick] or [ray_intersect] methods (ie. combined with the [IntersectionMaterial]),
 /// it needs to support `flat out int instance_id;`. Simply set it to the built-in glsl variable: `gl_InstanceID`.
@@ -259,12 +262,16 @@ pub enum IndexBuffer {
 }
 
 struct BaseMesh {
+    context: Context,
     indices: IndexBuffer,
     positions: VertexBuffer<Vec3>,
     normals: Option<VertexBuffer<Vec3>>,
     tangents: Option<VertexBuffer<Vec4>>,
     uvs: Option<VertexBuffer<Vec2>>,
+    uvs2: Option<VertexBuffer<Vec2>>,
     colors: Option<VertexBuffer<Vec4>>,
+    /// A copy of an indexed mesh where the triangles do not share vertices, which is created the first time it is needed for generating barycentric coordinates.
+    unindexed: OnceLock<Box<BaseMesh>>,
 }
 
 impl BaseMesh {
@@ -273,6 +280,7 @@ impl BaseMesh {
         cpu_mesh.validate().expect("invalid cpu mesh");
 
         Self {
+            context: context.clone(),
             indices: match &cpu_mesh.indices {
                 Indices::U8(ind) => IndexBuffer::U8(ElementBuffer::new_with_data(context, ind)),
                 Indices::U16(ind) => IndexBuffer::U16(ElementBuffer::new_with_data(context, ind)),
@@ -288,25 +296,101 @@ impl BaseMesh {
                 .tangents
                 .as_ref()
                 .map(|data| VertexBuffer::new_with_data(context, data)),
-            uvs: cpu_mesh.uvs.as_ref().map(|data| {
-                VertexBuffer::new_with_data(
-                    context,
-                    &data
-                        .iter()
-                        .map(|uv| vec2(uv.x, 1.0 - uv.y))
-                        .collect::<Vec<_>>(),
-                )
-            }),
+            uvs: cpu_mesh
+                .uvs
+                .as_ref()
+                .map(|data| Self::uv_buffer(context, data)),
+            // TODO: Import the second set of uv coordinates when three-d-asset adds it to CpuMesh and reads it from glTF files (TEXCOORD_1)
+            uvs2: None,
             colors: cpu_mesh.colors.as_ref().map(|data| {
                 VertexBuffer::new_with_data(
                     context,
                     &data.iter().map(|c| c.to_linear_srgb()).collect::<Vec<_>>(),
                 )
             }),
+            unindexed: OnceLock::new(),
         }
     }
 
+    fn uv_buffer(context: &Context, uvs: &[Vec2]) -> VertexBuffer<Vec2> {
+        VertexBuffer::new_with_data(
+            context,
+            &uvs.iter()
+                .map(|uv| vec2(uv.x, 1.0 - uv.y))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn set_uvs2(&mut self, context: &Context, uvs2: &[Vec2]) {
+        #[cfg(debug_assertions)]
+        if uvs2.len() as u32 != self.positions.vertex_count() {
+            panic!(
+                "invalid second set of uv coordinates, expected {} uv coordinates but got {}",
+                self.positions.vertex_count(),
+                uvs2.len()
+            );
+        }
+        self.uvs2 = Some(Self::uv_buffer(context, uvs2));
+        self.clear_unindexed();
+    }
+
+    ///
+    /// Must be called when any of the buffers are changed, since the unindexed copy is then no longer valid.
+    ///
+    pub fn clear_unindexed(&mut self) {
+        self.unindexed.take();
+    }
+
+    ///
+    /// Returns the unindexed copy if the mesh has indices and the program uses barycentric coordinates.
+    /// The barycentric coordinates are generated from the vertex index, which only works if the triangles do not share vertices,
+    /// and the `unindexed` uniform is only active if the barycentric coordinates are used.
+    ///
+    fn unindexed(&self, program: &Program) -> Option<&BaseMesh> {
+        if matches!(self.indices, IndexBuffer::None) || !program.requires_uniform("unindexed") {
+            return None;
+        }
+        Some(self.unindexed.get_or_init(|| {
+            let indices = match &self.indices {
+                IndexBuffer::None => unreachable!(),
+                IndexBuffer::U8(buffer) => buffer.read().iter().map(|i| i.as_u32()).collect(),
+                IndexBuffer::U16(buffer) => buffer.read().iter().map(|i| i.as_u32()).collect(),
+                IndexBuffer::U32(buffer) => buffer.read(),
+            };
+            let context = &self.context;
+            Box::new(Self {
+                context: context.clone(),
+                indices: IndexBuffer::None,
+                positions: unindex_buffer(context, &self.positions, &indices),
+                normals: self
+                    .normals
+                    .as_ref()
+                    .map(|buffer| unindex_buffer(context, buffer, &indices)),
+                tangents: self
+                    .tangents
+                    .as_ref()
+                    .map(|buffer| unindex_buffer(context, buffer, &indices)),
+                uvs: self
+                    .uvs
+                    .as_ref()
+                    .map(|buffer| unindex_buffer(context, buffer, &indices)),
+                uvs2: self
+                    .uvs2
+                    .as_ref()
+                    .map(|buffer| unindex_buffer(context, buffer, &indices)),
+                colors: self
+                    .colors
+                    .as_ref()
+                    .map(|buffer| unindex_buffer(context, buffer, &indices)),
+                unindexed: OnceLock::new(),
+            })
+        }))
+    }
+
     pub fn draw(&self, program: &Program, render_states: RenderStates, viewer: &dyn Viewer) {
+        if let Some(unindexed) = self.unindexed(program) {
+            return unindexed.draw(program, render_states, viewer);
+        }
         self.use_attributes(program);
 
         match &self.indices {
@@ -334,6 +418,9 @@ impl BaseMesh {
         viewer: &dyn Viewer,
         instance_count: u32,
     ) {
+        if let Some(unindexed) = self.unindexed(program) {
+            return unindexed.draw_instanced(program, render_states, viewer, instance_count);
+        }
         self.use_attributes(program);
 
         match &self.indices {
@@ -385,16 +472,28 @@ impl BaseMesh {
             }
         }
 
+        if program.requires_attribute("uv_coordinates2") {
+            if let Some(uvs2) = &self.uvs2 {
+                program.use_vertex_attribute("uv_coordinates2", uvs2);
+            }
+        }
+
         if program.requires_attribute("color") {
             if let Some(colors) = &self.colors {
                 program.use_vertex_attribute("color", colors);
             }
         }
+
+        // Barycentric coordinates can only be generated from the vertex index if the triangles do not share vertices, see BaseMesh::unindexed
+        program.use_uniform_if_required(
+            "unindexed",
+            matches!(self.indices, IndexBuffer::None) as i32,
+        );
     }
 
     fn vertex_shader_source(&self) -> String {
         format!(
-            "{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}",
             if self.normals.is_some() {
                 "#define USE_NORMALS\n"
             } else {
@@ -410,6 +509,11 @@ impl BaseMesh {
             } else {
                 ""
             },
+            if self.uvs2.is_some() {
+                "#define USE_UVS2\n"
+            } else {
+                ""
+            },
             if self.colors.is_some() {
                 "#define USE_VERTEX_COLORS\n"
             } else {
@@ -420,3 +524,18 @@ impl BaseMesh {
         )
     }
 }
+
+fn unindex_buffer<T: BufferDataType>(
+    context: &Context,
+    buffer: &VertexBuffer<T>,
+    indices: &[u32],
+) -> VertexBuffer<T> {
+    let data = buffer.read();
+    VertexBuffer::new_with_data(
+        context,
+        &indices
+            .iter()
+            .map(|i| data[*i as usize].clone())
+            .collect::<Vec<_>>(),
+    )
+}
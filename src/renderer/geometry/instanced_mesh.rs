@@ -90,6 +90,14 @@ impl InstancedMesh {
         self.instances.count()
     }
 
+    ///
+    /// Sets a second set of uv coordinates, one for each vertex, which is shared by all instances, see [Mesh::set_uvs2].
+    /// Note that the texture transformations of the instances are only applied to the first set of uv coordinates.
+    ///
+    pub fn set_uvs2(&mut self, uvs2: &[Vec2]) {
+        self.base_mesh.set_uvs2(&self.context, uvs2);
+    }
+
     ///
     /// Update the instances.
     ///
@@ -170,8 +178,10 @@ impl<'a> IntoIterator for &'a InstancedMesh {
 
 impl Geometry for InstancedMesh {
     fn draw(&self, viewer: &dyn Viewer, program: &Program, render_states: RenderStates) {
-        // Check if we need a reorder the instance draw order. This only applies to transparent materials.
+        // Check if we need a reorder the instance draw order. This only applies to transparent materials
+        // that are not rendered with order-independent transparency.
         if render_states.blend != Blend::Disabled
+            && render_states.blend != order_independent_transparency::ACCUMULATION_BLEND
             && self
                 .last_camera_position
                 .read()
@@ -249,6 +259,7 @@ impl Geometry for InstancedMesh {
             self.base_mesh.colors.is_some(),
             self.instance_color.read().unwrap().is_some(),
             self.tex_transform.read().unwrap().is_some(),
+            self.base_mesh.uvs2.is_some(),
         )
     }
 
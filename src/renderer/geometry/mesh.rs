@@ -90,6 +90,7 @@ impl Mesh {
     /// Note: Changing this will possibly ruin the mesh.
     ///
     pub fn indices_mut(&mut self) -> &mut IndexBuffer {
+        self.base_mesh.clear_unindexed();
         &mut self.base_mesh.indices
     }
 
@@ -98,6 +99,7 @@ impl Mesh {
     /// Note: Changing this will possibly ruin the mesh.
     ///
     pub fn positions_mut(&mut self) -> &mut VertexBuffer<Vec3> {
+        self.base_mesh.clear_unindexed();
         &mut self.base_mesh.positions
     }
 
@@ -106,6 +108,7 @@ impl Mesh {
     /// Note: Changing this will possibly ruin the mesh.
     ///
     pub fn normals_mut(&mut self) -> &mut Option<VertexBuffer<Vec3>> {
+        self.base_mesh.clear_unindexed();
         &mut self.base_mesh.normals
     }
 
@@ -114,14 +117,36 @@ impl Mesh {
     /// Note: Changing this will possibly ruin the mesh.
     ///
     pub fn uvs_mut(&mut self) -> &mut Option<VertexBuffer<Vec2>> {
+        self.base_mesh.clear_unindexed();
         &mut self.base_mesh.uvs
     }
 
+    ///
+    /// Sets a second set of uv coordinates, one for each vertex, which is typically used for mapping a lightmap
+    /// (see [PhysicalMaterial::lightmap_texture]) since the lightmap must not overlap itself as opposed to most other textures.
+    /// Like the uv coordinates in the [CpuMesh], the v coordinate is flipped before the uv coordinates are transferred to the GPU.
+    /// A [CpuMesh] only contains one set of uv coordinates, so the second set of uv coordinates in for example a glTF file (`TEXCOORD_1`)
+    /// is not loaded together with the mesh and must be set using this method.
+    ///
+    pub fn set_uvs2(&mut self, uvs2: &[Vec2]) {
+        self.base_mesh.set_uvs2(&self.context, uvs2);
+    }
+
+    ///
+    /// Used for editing the second set of vertex uvs, see [Mesh::set_uvs2].
+    /// Note: Changing this will possibly ruin the mesh.
+    ///
+    pub fn uvs2_mut(&mut self) -> &mut Option<VertexBuffer<Vec2>> {
+        self.base_mesh.clear_unindexed();
+        &mut self.base_mesh.uvs2
+    }
+
     ///
     /// Used for editing the vertex tangents.
     /// Note: Changing this will possibly ruin the mesh.
     ///
     pub fn tangents_mut(&mut self) -> &mut Option<VertexBuffer<Vec4>> {
+        self.base_mesh.clear_unindexed();
         &mut self.base_mesh.tangents
     }
 
@@ -130,6 +155,7 @@ impl Mesh {
     /// Note: Changing this will possibly ruin the mesh.
     ///
     pub fn colors_mut(&mut self) -> &mut Option<VertexBuffer<Vec4>> {
+        self.base_mesh.clear_unindexed();
         &mut self.base_mesh.colors
     }
 }
@@ -178,6 +204,7 @@ impl Geometry for Mesh {
             self.base_mesh.tangents.is_some(),
             self.base_mesh.uvs.is_some(),
             self.base_mesh.colors.is_some(),
+            self.base_mesh.uvs2.is_some(),
         )
     }
 
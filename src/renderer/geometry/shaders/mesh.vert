@@ -41,6 +41,11 @@ in vec2 uv_coordinates;
 out vec2 uvs;
 #endif
 
+#ifdef USE_UVS2
+in vec2 uv_coordinates2;
+out vec2 uvs2;
+#endif
+
 #ifdef USE_VERTEX_COLORS 
 in vec4 color;
 #endif
@@ -48,6 +53,9 @@ in vec4 color;
 in vec4 instance_color;
 #endif
 
+uniform int unindexed;
+out vec3 barycentric;
+
 out vec4 col;
 flat out int instance_id;
 
@@ -101,6 +109,9 @@ void main()
 #else
     uvs = uv_coordinates;
 #endif
+#endif
+#ifdef USE_UVS2
+    uvs2 = uv_coordinates2;
 #endif
 
     // *** COLOR ***
@@ -112,4 +123,14 @@ void main()
     col *= instance_color;
 #endif
     instance_id = gl_InstanceID;
+
+    // *** BARYCENTRIC COORDINATES ***
+    if (unindexed == 1) {
+        // Each triangle consists of three consecutive vertices, since the triangles do not share vertices
+        int corner = gl_VertexID % 3;
+        barycentric = vec3(corner == 0, corner == 1, corner == 2);
+    } else {
+        // Only the case when the barycentric coordinates are not used, since an indexed mesh is otherwise drawn without indices
+        barycentric = vec3(1.0);
+    }
 }
\ No newline at end of file
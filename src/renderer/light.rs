@@ -20,6 +20,7 @@ macro_rules! impl_light_body {
 
 mod directional_light;
 use std::ops::Deref;
+use std::sync::Arc;
 
 #[doc(inline)]
 pub use directional_light::*;
@@ -28,6 +29,10 @@ mod spot_light;
 #[doc(inline)]
 pub use spot_light::*;
 
+mod photometric_profile;
+#[doc(inline)]
+pub use photometric_profile::*;
+
 mod point_light;
 #[doc(inline)]
 pub use point_light::*;
@@ -36,13 +41,45 @@ mod ambient_light;
 #[doc(inline)]
 pub use ambient_light::*;
 
+mod hemisphere_light;
+#[doc(inline)]
+pub use hemisphere_light::*;
+
 mod environment;
 #[doc(inline)]
 pub use environment::*;
 
+mod spherical_harmonics;
+#[doc(inline)]
+pub use spherical_harmonics::*;
+
+mod reflection_probes;
+#[doc(inline)]
+pub use reflection_probes::*;
+
+mod rectangle_light;
+#[doc(inline)]
+pub use rectangle_light::*;
+
+mod disk_light;
+#[doc(inline)]
+pub use disk_light::*;
+
+mod buffered_lights;
+#[doc(inline)]
+pub use buffered_lights::*;
+
+mod clustered_lights;
+#[doc(inline)]
+pub use clustered_lights::*;
+
 use crate::core::*;
 use crate::renderer::viewer::*;
 use crate::renderer::LightId;
+use crate::renderer::{
+    apply_screen_effect, render_with_material, CopyEffect, DepthMaterial, Geometry, MaterialType,
+    Object, RendererError, TransmittanceMaterial,
+};
 
 ///
 /// Specifies how the intensity of a light fades over distance.
@@ -58,6 +95,29 @@ pub struct Attenuation {
     pub quadratic: f32,
 }
 
+impl Attenuation {
+    ///
+    /// Returns the distance from a light with the given intensity at which the attenuated intensity drops below 1/256,
+    /// which means that the light has no visible effect beyond that distance.
+    /// Returns [f32::INFINITY] if the intensity never drops below 1/256.
+    ///
+    pub fn range(&self, intensity: f32) -> f32 {
+        // Solve quadratic * d^2 + linear * d + constant = 256 * intensity for d
+        let target = 256.0 * intensity;
+        if self.constant >= target {
+            0.0
+        } else if self.quadratic > 0.0 {
+            let discriminant =
+                self.linear * self.linear - 4.0 * self.quadratic * (self.constant - target);
+            (-self.linear + discriminant.sqrt()) / (2.0 * self.quadratic)
+        } else if self.linear > 0.0 {
+            (target - self.constant) / self.linear
+        } else {
+            f32::INFINITY
+        }
+    }
+}
+
 impl Default for Attenuation {
     fn default() -> Self {
         Self {
@@ -68,6 +128,143 @@ impl Default for Attenuation {
     }
 }
 
+/// The maximum kernel size of [ShadowFilter::Pcf].
+pub const MAX_PCF_KERNEL_SIZE: u32 = 9;
+
+///
+/// The filter used when sampling a shadow map which determines how soft the edges of the shadows are.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShadowFilter {
+    /// A single sample per fragment which results in hard and aliased shadow edges.
+    None,
+    /// Percentage-closer filtering which averages the shadow test of `kernel_size` x `kernel_size` texels around the fragment.
+    Pcf {
+        /// The number of texels in each direction.
+        /// The number of samples grows quadratically with the kernel size, so it is clamped to the range from 1 to [MAX_PCF_KERNEL_SIZE].
+        kernel_size: u32,
+    },
+    /// Averages the shadow test of four samples placed in a Poisson disk around the fragment.
+    Poisson {
+        /// The radius of the disk in shadow map texture coordinates.
+        radius: f32,
+        /// Whether to rotate the disk randomly for each fragment, which replaces banding with noise.
+        rotated: bool,
+    },
+    /// Percentage-closer soft shadows where the filter size depends on the distance between the occluder and the receiving surface.
+    /// This simulates the penumbra of a light with an area, so the shadows are sharp close to the occluder and soft further away.
+    Pcss {
+        /// The size of the light in shadow map texture coordinates.
+        light_size: f32,
+    },
+}
+
+impl ShadowFilter {
+    ///
+    /// Returns the fragment shader call which returns the visibility in the range `[0..1]` using this filter.
+    ///
+    fn shader_call(
+        &self,
+        shadow_map: &str,
+        layer: &str,
+        shadow_coord: &str,
+        parameters: &str,
+    ) -> String {
+        let function = match self {
+            Self::None => "shadow_filter_none",
+            Self::Pcf { .. } => "shadow_filter_pcf",
+            Self::Poisson { .. } => "shadow_filter_poisson",
+            Self::Pcss { .. } => "shadow_filter_pcss",
+        };
+        format!(
+            "{}({}, {}, {}, {})",
+            function, shadow_map, layer, shadow_coord, parameters
+        )
+    }
+
+    fn parameters(&self) -> Vec4 {
+        match *self {
+            Self::None => Vec4::zero(),
+            Self::Pcf { kernel_size } => vec4(
+                kernel_size.clamp(1, MAX_PCF_KERNEL_SIZE) as f32,
+                0.0,
+                0.0,
+                0.0,
+            ),
+            Self::Poisson { radius, rotated } => {
+                vec4(radius, if rotated { 1.0 } else { 0.0 }, 0.0, 0.0)
+            }
+            Self::Pcss { light_size } => vec4(light_size, 0.0, 0.0, 0.0),
+        }
+    }
+
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Pcf { .. } => 1,
+            Self::Poisson { .. } => 2,
+            Self::Pcss { .. } => 3,
+        }
+    }
+}
+
+impl Default for ShadowFilter {
+    fn default() -> Self {
+        Self::Poisson {
+            radius: 0.001,
+            rotated: false,
+        }
+    }
+}
+
+///
+/// Controls the offsets applied when comparing a fragment with the depth in a shadow map.
+/// Too little bias results in shadow acne (self-shadowing stripes) while too much bias results in peter-panning (shadows detached from the caster).
+///
+/// The depth bias is `max(constant, angle_scaled * (1 - cos(angle))) + slope_scaled * tan(angle)`, where `angle` is the angle between the surface normal and the light direction.
+/// The default only uses the constant and angle scaled bias, the slope scaled bias, normal offset and polygon offset are zero and have to be enabled explicitly.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowBias {
+    /// The minimum depth bias, in shadow map depth units.
+    pub constant: f32,
+    /// Depth bias, in shadow map depth units, which is scaled by one minus the cosine of the angle between the surface normal and the light direction.
+    /// Only used if it is larger than the constant bias.
+    pub angle_scaled: f32,
+    /// Depth bias, in shadow map depth units, which is scaled by the tangent of the angle between the surface normal and the light direction and added to the other depth bias.
+    pub slope_scaled: f32,
+    /// The distance in world space the fragment position is moved along the surface normal before it is projected into the shadow map.
+    pub normal_offset: f32,
+    /// The polygon offset factor applied when rendering the shadow map. Both the factor and units have to be zero to disable the polygon offset.
+    pub polygon_offset_factor: f32,
+    /// The polygon offset units applied when rendering the shadow map. Both the factor and units have to be zero to disable the polygon offset.
+    pub polygon_offset_units: f32,
+}
+
+impl ShadowBias {
+    fn parameters(&self) -> Vec4 {
+        vec4(
+            self.constant,
+            self.angle_scaled,
+            self.slope_scaled,
+            self.normal_offset,
+        )
+    }
+}
+
+impl Default for ShadowBias {
+    fn default() -> Self {
+        Self {
+            constant: 0.005,
+            angle_scaled: 0.05,
+            slope_scaled: 0.0,
+            normal_offset: 0.0,
+            polygon_offset_factor: 0.0,
+            polygon_offset_units: 0.0,
+        }
+    }
+}
+
 /// Represents a light source.
 pub trait Light {
     /// The fragment shader source for calculating this lights contribution to the color in a fragment.
@@ -133,10 +330,22 @@ impl<T: Light> Light for std::sync::Arc<std::sync::RwLock<T>> {
 /// ```no_rust
 /// vec3 calculate_lighting(vec3 camera_position, vec3 surface_color, vec3 position, vec3 normal, float metallic, float roughness, float occlusion)
 /// ```
+/// The index of refraction, specular, clearcoat, sheen and transmission parameters of the surface are given by global variables,
+/// for example `material_clearcoat`, which can be assigned before calling `calculate_lighting` and otherwise have no effect.
 ///
 pub fn lights_shader_source(lights: &[&dyn Light]) -> String {
     let mut shader_source = include_str!("../core/shared.frag").to_string();
     shader_source.push_str(include_str!("light/shaders/light_shared.frag"));
+    shader_source.push_str(include_str!("light/shaders/ltc.frag"));
+    for (shadow_map, uv) in [("sampler2D", "uv"), ("sampler2DArray", "vec3(uv, layer)")] {
+        shader_source.push_str(&format!(
+            "#define SHADOW_MAP {}\n#define SHADOW_UV(uv, layer) {}\n",
+            shadow_map, uv
+        ));
+        shader_source.push_str(include_str!("light/shaders/shadow_filter.frag"));
+        shader_source.push_str("#undef SHADOW_MAP\n#undef SHADOW_UV\n");
+    }
+    shader_source.push_str(include_str!("light/shaders/shadow_compatibility.frag"));
     let mut dir_fun = String::new();
     for (i, light) in lights.iter().enumerate() {
         shader_source.push_str(&light.shader_source(i as u32));
@@ -164,6 +373,400 @@ fn shadow_matrix(camera: &Camera) -> Mat4 {
     bias_matrix * camera.projection() * camera.view()
 }
 
+///
+/// Renders the geometries into the depth target as seen from the shadow camera.
+/// Each geometry is rendered with the depth material returned by the given function, for example [Object::depth_material], or a default [DepthMaterial] if it returns `None`.
+/// If a background is given, the depth of the background is copied into the depth target before rendering the geometries.
+///
+fn render_shadow_map<G: Geometry>(
+    context: &Context,
+    shadow_camera: &Camera,
+    depth_target: DepthTarget,
+    geometries: impl IntoIterator<Item = G>,
+    depth_material: impl Fn(&G) -> Option<DepthMaterial>,
+    bias: &ShadowBias,
+    background: Option<&DepthTexture2D>,
+) {
+    let render_states = RenderStates {
+        write_mask: WriteMask::DEPTH,
+        ..Default::default()
+    };
+    let frustum = shadow_camera.frustum();
+    depth_target
+        .clear(ClearState::default())
+        .write::<RendererError>(|| {
+            if let Some(background) = background {
+                apply_screen_effect(
+                    context,
+                    CopyEffect {
+                        write_mask: WriteMask::DEPTH,
+                        ..Default::default()
+                    },
+                    shadow_camera,
+                    &[],
+                    None,
+                    Some(DepthTexture::Single(background)),
+                );
+            }
+            context.set_polygon_offset(bias.polygon_offset_factor, bias.polygon_offset_units);
+            for geometry in geometries
+                .into_iter()
+                .filter(|g| frustum.contains(g.aabb()))
+            {
+                let material = DepthMaterial {
+                    render_states,
+                    ..depth_material(&geometry).unwrap_or_default()
+                };
+                render_with_material(context, shadow_camera, &geometry, &material, &[]);
+            }
+            context.set_polygon_offset(0.0, 0.0);
+            Ok(())
+        })
+        .unwrap();
+}
+
+///
+/// The depth material of a geometry given to a shadow map function, which is always the default [DepthMaterial].
+///
+fn geometry_depth_material<G: Geometry>(_geometry: &G) -> Option<DepthMaterial> {
+    None
+}
+
+///
+/// The depth material of an object given to a shadow map function, see [Object::depth_material].
+///
+fn object_depth_material<O: Object>(object: &O) -> Option<DepthMaterial> {
+    object.depth_material()
+}
+
+///
+/// Returns the given shadow texture if it has the given size, otherwise a new shadow texture with the given size.
+///
+fn reuse_shadow_texture(
+    context: &Context,
+    texture: Option<DepthTexture2D>,
+    texture_size: u32,
+) -> DepthTexture2D {
+    match texture {
+        Some(texture) if texture.width() == texture_size && texture.height() == texture_size => {
+            texture
+        }
+        _ => DepthTexture2D::new::<f32>(
+            context,
+            texture_size,
+            texture_size,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        ),
+    }
+}
+
+///
+/// A shadow map of the static shadow casters together with the state of the shadow casters when the shadow map was last updated.
+/// This is used to avoid rendering the shadow map when nothing has changed and to only render the dynamic shadow casters when only they have changed.
+///
+#[derive(Default)]
+struct ShadowCache {
+    static_texture: Option<DepthTexture2D>,
+    static_state: Vec<f32>,
+    dynamic_state: Vec<f32>,
+    static_changed: bool,
+    dynamic_changed: bool,
+}
+
+impl ShadowCache {
+    ///
+    /// Forces the static geometries to be rendered again at the next update, regardless of whether their state has changed.
+    ///
+    fn mark_static_changed(&mut self) {
+        self.static_changed = true;
+    }
+
+    ///
+    /// Forces the dynamic geometries to be rendered again at the next update, regardless of whether their state has changed.
+    ///
+    fn mark_dynamic_changed(&mut self) {
+        self.dynamic_changed = true;
+    }
+
+    ///
+    /// Renders the static and dynamic geometries into the shadow texture if the shadow camera, the bias or the bounding box of any of the geometries have changed since the last update
+    /// or if the geometries have been marked as changed.
+    /// The static geometries are only rendered into the cache when the shadow camera, the bias or any of the static geometries have changed,
+    /// otherwise the cache is copied into the shadow texture and only the dynamic geometries are rendered on top.
+    ///
+    fn update<S: Geometry, D: Geometry>(
+        &mut self,
+        context: &Context,
+        shadow_camera: &Camera,
+        shadow_texture: &mut DepthTexture2D,
+        static_geometries: impl IntoIterator<Item = S>,
+        dynamic_geometries: impl IntoIterator<Item = D> + Clone,
+        static_depth_material: impl Fn(&S) -> Option<DepthMaterial>,
+        dynamic_depth_material: impl Fn(&D) -> Option<DepthMaterial>,
+        bias: &ShadowBias,
+    ) {
+        let static_geometries = static_geometries.into_iter().collect::<Vec<_>>();
+        let viewport = shadow_camera.viewport();
+        let mut static_state = vec![
+            viewport.width as f32,
+            viewport.height as f32,
+            bias.polygon_offset_factor,
+            bias.polygon_offset_units,
+        ];
+        let shadow_matrix = shadow_camera.projection() * shadow_camera.view();
+        let shadow_matrix: &[f32; 16] = shadow_matrix.as_ref();
+        static_state.extend(shadow_matrix);
+        static_state.extend(static_geometries.iter().flat_map(|g| aabb_state(g.aabb())));
+        let dynamic_state = dynamic_geometries
+            .clone()
+            .into_iter()
+            .flat_map(|g| aabb_state(g.aabb()))
+            .collect::<Vec<_>>();
+
+        let static_changed = self.static_changed
+            || self.static_state != static_state
+            || self.static_texture.is_none();
+        if !static_changed && !self.dynamic_changed && self.dynamic_state == dynamic_state {
+            return;
+        }
+        if static_changed {
+            let mut static_texture =
+                reuse_shadow_texture(context, self.static_texture.take(), viewport.width);
+            render_shadow_map(
+                context,
+                shadow_camera,
+                static_texture.as_depth_target(),
+                static_geometries,
+                static_depth_material,
+                bias,
+                None,
+            );
+            self.static_texture = Some(static_texture);
+        }
+        render_shadow_map(
+            context,
+            shadow_camera,
+            shadow_texture.as_depth_target(),
+            dynamic_geometries,
+            dynamic_depth_material,
+            bias,
+            self.static_texture.as_ref(),
+        );
+        self.static_state = static_state;
+        self.dynamic_state = dynamic_state;
+        self.static_changed = false;
+        self.dynamic_changed = false;
+    }
+}
+
+fn aabb_state(aabb: AxisAlignedBoundingBox) -> [f32; 6] {
+    let (min, max) = (aabb.min(), aabb.max());
+    [min.x, min.y, min.z, max.x, max.y, max.z]
+}
+
+///
+/// Returns the material used to render the transmittance of the given object if it is transparent, otherwise the object casts a solid shadow.
+///
+fn transmittance_material(object: &impl Object) -> Option<TransmittanceMaterial> {
+    if object.material_type() == MaterialType::Transparent {
+        object.transmittance_material()
+    } else {
+        None
+    }
+}
+
+///
+/// The transmittance of the transparent shadow casters as seen from a light, ie. the fraction of the light of each color channel passing through all of them,
+/// together with the depth of the transparent shadow caster closest to the light.
+///
+struct TransmittanceMap {
+    texture: Texture2D,
+    depth_texture: DepthTexture2D,
+}
+
+impl TransmittanceMap {
+    ///
+    /// Renders the transmittance of the transparent objects as seen from the shadow camera or returns `None` if none of the objects are transparent.
+    /// The given transmittance map is reused if it has the same size as the viewport of the shadow camera.
+    ///
+    fn render(
+        context: &Context,
+        shadow_camera: &Camera,
+        transmittance_map: Option<Self>,
+        objects: impl IntoIterator<Item = impl Object>,
+        bias: &ShadowBias,
+    ) -> Option<Self> {
+        let frustum = shadow_camera.frustum();
+        let casters = objects
+            .into_iter()
+            .filter(|o| frustum.contains(o.aabb()))
+            .filter_map(|o| transmittance_material(&o).map(|material| (o, material)))
+            .collect::<Vec<_>>();
+        if casters.is_empty() {
+            return None;
+        }
+        let size = shadow_camera.viewport().width;
+        let mut transmittance_map = match transmittance_map {
+            Some(map) if map.texture.width() == size && map.texture.height() == size => map,
+            _ => Self {
+                texture: Texture2D::new_empty::<[u8; 4]>(
+                    context,
+                    size,
+                    size,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                depth_texture: reuse_shadow_texture(context, None, size),
+            },
+        };
+        RenderTarget::new(
+            transmittance_map.texture.as_color_target(None),
+            transmittance_map.depth_texture.as_depth_target(),
+        )
+        .clear(ClearState::color_and_depth(1.0, 1.0, 1.0, 1.0, 1.0))
+        .write::<RendererError>(|| {
+            context.set_polygon_offset(bias.polygon_offset_factor, bias.polygon_offset_units);
+            for (object, material) in casters.iter() {
+                // Multiply the transmittance of all casters, independent of the order
+                render_with_material(context, shadow_camera, object, material, &[]);
+                // Keep the depth of the closest caster, so only surfaces behind it are affected
+                let depth_material = TransmittanceMaterial {
+                    render_states: RenderStates {
+                        write_mask: WriteMask::DEPTH,
+                        cull: Cull::None,
+                        ..Default::default()
+                    },
+                    ..material.clone()
+                };
+                render_with_material(context, shadow_camera, object, &depth_material, &[]);
+            }
+            context.set_polygon_offset(0.0, 0.0);
+            Ok(())
+        })
+        .unwrap();
+        Some(transmittance_map)
+    }
+
+    ///
+    /// Returns the uniform declarations and the factor multiplied with the light in the shader of the light with the given index.
+    /// Both are empty if there is no transmittance map.
+    ///
+    fn shader_source(transmittance_map: Option<&Self>, i: u32) -> (String, String) {
+        if transmittance_map.is_some() {
+            (
+                format!(
+                    "uniform sampler2D transmittanceMap{};\nuniform sampler2D transmittanceDepthMap{};",
+                    i, i
+                ),
+                format!(
+                    " * shadow_transmittance(transmittanceMap{}, transmittanceDepthMap{}, coord)",
+                    i, i
+                ),
+            )
+        } else {
+            (String::new(), String::new())
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        program.use_texture(&format!("transmittanceMap{}", i), &self.texture);
+        program.use_depth_texture(&format!("transmittanceDepthMap{}", i), &self.depth_texture);
+    }
+}
+
+///
+/// The lookup tables used for lighting from area lights using linearly transformed cosines (LTC).
+/// The tables are fitted to the GGX distribution and indexed by the roughness (u) and `sqrt(1 - dot(normal, view_direction))` (v).
+/// They are created the first time an area light is created and then shared between all area lights using the same [Context].
+///
+/// The data in `light/data/ltc_matrix.bin` and `light/data/ltc_amplitude.bin` is 64x64 little-endian 32 bit floats, four and two per entry respectively.
+/// It is generated by `light/data/ltc_fit.rs`, a standalone implementation of the fitting procedure in
+/// 'Real-Time Polygonal-Light Shading with Linearly Transformed Cosines' by Heitz, Dupuy, Hill and Neubelt (2016),
+/// which documents the parameters of the fit and how to run it.
+/// Both the program and the data are distributed under the same MIT license as the rest of the crate.
+///
+struct LtcTables {
+    /// The four non-trivial entries of the inverse LTC matrix.
+    matrix: Texture2D,
+    /// The amplitude of the BRDF split into the part scaled by the specular color and the fresnel part.
+    amplitude: Texture2D,
+}
+
+impl LtcTables {
+    const SIZE: u32 = 64;
+
+    ///
+    /// Returns the tables stored in the context, or creates them and stores them in the context if they do not exist yet.
+    ///
+    fn shared(context: &Context) -> Arc<Self> {
+        let tables = context
+            .take_resource::<Arc<Self>>()
+            .unwrap_or_else(|| Arc::new(Self::new(context)));
+        context.set_resource(Box::new(tables.clone()));
+        tables
+    }
+
+    fn new(context: &Context) -> Self {
+        Self {
+            matrix: Self::texture::<[f16; 4]>(context, include_bytes!("light/data/ltc_matrix.bin")),
+            amplitude: Self::texture::<[f16; 2]>(
+                context,
+                include_bytes!("light/data/ltc_amplitude.bin"),
+            ),
+        }
+    }
+
+    ///
+    /// Creates a texture from the given 32 bit float data.
+    /// The data is converted to 16 bit floats, since linear interpolation of 32 bit float textures is not supported on all devices, for example on many WebGL2 devices.
+    ///
+    fn texture<T: TextureDataType + Default + AsMut<[f16]>>(
+        context: &Context,
+        bytes: &[u8],
+    ) -> Texture2D {
+        let data = bytes
+            .chunks(2 * std::mem::size_of::<T>())
+            .map(|chunk| {
+                let mut value = T::default();
+                for (v, b) in value.as_mut().iter_mut().zip(chunk.chunks(4)) {
+                    *v = f16::from_f32(f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+                }
+                value
+            })
+            .collect::<Vec<_>>();
+        let mut texture = Texture2D::new_empty::<T>(
+            context,
+            Self::SIZE,
+            Self::SIZE,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        // The tables start at normal incidence which should be the bottom row of the texture
+        texture.fill(&flip_rows(&data, Self::SIZE as usize));
+        texture
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        program.use_texture(&format!("ltcMatrix{}", i), &self.matrix);
+        program.use_texture(&format!("ltcAmplitude{}", i), &self.amplitude);
+    }
+}
+
+///
+/// Returns the given texture data with the rows in reverse order.
+/// Used for data where the first row should be the bottom of the texture, since [Texture2D::fill] expects the first row to be the top of the texture.
+///
+fn flip_rows<T: Clone>(data: &[T], width: usize) -> Vec<T> {
+    data.chunks(width).rev().flatten().cloned().collect()
+}
+
 fn compute_up_direction(direction: Vec3) -> Vec3 {
     if vec3(1.0, 0.0, 0.0).dot(direction).abs() > 0.9 {
         (vec3(0.0, 1.0, 0.0).cross(direction)).normalize()
@@ -3,7 +3,8 @@ use crate::renderer::*;
 
 ///
 /// A light which shines on all surfaces.
-/// Can be uniform (a light that shines equally on any surface) or calculated from an environment map using the [Environment] struct.
+/// Can be uniform (a light that shines equally on any surface) or calculated from an environment map using the [Environment] struct
+/// and/or [SphericalHarmonics].
 ///
 pub struct AmbientLight {
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
@@ -12,6 +13,9 @@ pub struct AmbientLight {
     pub color: Srgba,
     /// The light shining from the environment. This is calculated based on an environment map.
     pub environment: Option<Environment>,
+    /// The diffuse light shining from the environment represented by spherical harmonics.
+    /// If specified, this is used for the diffuse lighting instead of the irradiance map of the [Environment].
+    pub spherical_harmonics: Option<SphericalHarmonics>,
 }
 
 impl AmbientLight {
@@ -21,6 +25,7 @@ impl AmbientLight {
             intensity,
             color,
             environment: None,
+            spherical_harmonics: None,
         }
     }
 
@@ -35,16 +40,73 @@ impl AmbientLight {
             intensity,
             color,
             environment: Some(Environment::new(context, environment_map)),
+            spherical_harmonics: None,
+        }
+    }
+
+    ///
+    /// Constructs an ambient light that shines based on the given environment map in the same way as [AmbientLight::new_with_environment],
+    /// except that the diffuse light is represented by spherical harmonics projected from the environment map using [SphericalHarmonics::from_environment_map].
+    /// This skips the expensive convolution of the environment map into an irradiance map, see [Environment::new_without_irradiance_map],
+    /// so it is much faster to construct, for example when the environment map changes often.
+    ///
+    pub fn new_with_environment_and_spherical_harmonics(
+        context: &Context,
+        intensity: f32,
+        color: Srgba,
+        environment_map: &TextureCubeMap,
+    ) -> Self {
+        Self {
+            intensity,
+            color,
+            environment: Some(Environment::new_without_irradiance_map(
+                context,
+                environment_map,
+            )),
+            spherical_harmonics: Some(SphericalHarmonics::from_environment_map(
+                context,
+                environment_map,
+            )),
+        }
+    }
+
+    ///
+    /// Constructs an ambient light that shines based on the given spherical harmonics, for example projected from an environment map
+    /// using [SphericalHarmonics::from_environment_map] or calculated offline.
+    /// This is much cheaper than an ambient light using an [Environment], but the reflections are only a rough approximation,
+    /// so use [AmbientLight::new_with_environment_and_spherical_harmonics] for accurate reflections.
+    ///
+    pub fn new_with_spherical_harmonics(
+        intensity: f32,
+        color: Srgba,
+        spherical_harmonics: SphericalHarmonics,
+    ) -> Self {
+        Self {
+            intensity,
+            color,
+            environment: None,
+            spherical_harmonics: Some(spherical_harmonics),
         }
     }
 }
 
 impl Light for AmbientLight {
     fn shader_source(&self, i: u32) -> String {
+        let (irradiance_source, irradiance) = if self.spherical_harmonics.is_some() {
+            (
+                SPHERICAL_HARMONICS_SOURCE,
+                "spherical_harmonics_irradiance(N)",
+            )
+        } else {
+            (
+                "uniform samplerCube irradianceMap;",
+                "texture(irradianceMap, N).rgb",
+            )
+        };
         if self.environment.is_some() {
             format!(
             "
-                uniform samplerCube irradianceMap;
+                {}
                 uniform samplerCube prefilterMap;
                 uniform sampler2D brdfLUT;
                 uniform vec3 ambientColor;
@@ -57,32 +119,73 @@ impl Light for AmbientLight {
                     float NdV = max(0.001, dot(N, V));
                     
                     // calculate reflectance at normal incidence; if dia-electric (like plastic) use F0 
-                    // of 0.04 and if it's a metal, use the albedo color as F0 (metallic workflow)    
-                    vec3 F0 = mix(vec3(0.04), surface_color, metallic);
-                    vec3 specular_fresnel = fresnel_schlick_roughness(F0, NdV, roughness);
+                    // given by the index of refraction and if it's a metal, use the albedo color as F0 (metallic workflow)    
+                    vec3 specular_fresnel = surface_fresnel(surface_color, metallic, NdV, roughness);
                     vec3 diffuse_fresnel = 1.0 - specular_fresnel;
 
                     // Diffuse
-                    vec3 irradiance = texture(irradianceMap, N).rgb;
-                    vec3 diffuse = diffuse_fresnel * mix(surface_color, vec3(0.0), metallic) * irradiance;
+                    vec3 irradiance = {};
+                    vec3 diffuse = diffuse_fresnel * surface_diffuse(surface_color, metallic) * irradiance;
                     
                     // sample both the pre-filter map and the BRDF lut and combine them together as per the Split-Sum approximation to get the IBL specular part.
                     const float MAX_REFLECTION_LOD = 4.0;
                     vec3 prefilteredColor = textureLod(prefilterMap, R,  roughness * MAX_REFLECTION_LOD).rgb;    
                     vec2 brdf  = texture(brdfLUT, vec2(NdV, roughness)).rg;
                     vec3 specular = prefilteredColor * (specular_fresnel * brdf.x + brdf.y);
+
+                    vec3 sheen = sheen_reflection(irradiance);
+
+                    vec3 color = diffuse + specular + sheen;
+                    if (material_clearcoat > 0.0) {{
+                        vec3 clearcoat_reflection = reflect(-V, material_clearcoat_normal);
+                        vec3 clearcoat_color = textureLod(prefilterMap, clearcoat_reflection, material_clearcoat_roughness * MAX_REFLECTION_LOD).rgb;
+                        color = add_clearcoat(color, clearcoat_color * clearcoat_fresnel(max(0.001, dot(material_clearcoat_normal, V))), V);
+                    }}
     
-                    return (diffuse + specular) * occlusion * ambientColor;
+                    return color * occlusion * ambientColor * material_ambient_factor;
                 }}
             
-            ", i)
+            ", irradiance_source, i, irradiance)
+        } else if self.spherical_harmonics.is_some() {
+            format!(
+            "
+                {}
+                uniform vec3 ambientColor;
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    float NdV = max(0.001, dot(normal, view_direction));
+                    vec3 specular_fresnel = surface_fresnel(surface_color, metallic, NdV, roughness);
+                    vec3 diffuse_fresnel = 1.0 - specular_fresnel;
+
+                    // Diffuse
+                    vec3 irradiance = spherical_harmonics_irradiance(normal);
+                    vec3 diffuse = diffuse_fresnel * surface_diffuse(surface_color, metallic) * irradiance;
+
+                    // The reflections are approximated by the irradiance in a direction between the reflection and the normal depending on the roughness
+                    vec3 reflection = normalize(mix(reflect(-view_direction, normal), normal, roughness * roughness));
+                    vec3 specular = specular_fresnel * spherical_harmonics_irradiance(reflection);
+                    vec3 sheen = sheen_reflection(irradiance);
+
+                    vec3 color = diffuse + specular + sheen;
+                    if (material_clearcoat > 0.0) {{
+                        vec3 clearcoat_normal = material_clearcoat_normal;
+                        vec3 clearcoat_reflection = normalize(mix(reflect(-view_direction, clearcoat_normal), clearcoat_normal, material_clearcoat_roughness * material_clearcoat_roughness));
+                        vec3 clearcoat_color = spherical_harmonics_irradiance(clearcoat_reflection) * clearcoat_fresnel(max(0.001, dot(clearcoat_normal, view_direction)));
+                        color = add_clearcoat(color, clearcoat_color, view_direction);
+                    }}
+
+                    return color * occlusion * ambientColor * material_ambient_factor;
+                }}
+
+            ", irradiance_source, i)
         } else {
             format!(
                 "
                     uniform vec3 ambientColor;
                     vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
-                        return occlusion * ambientColor * mix(surface_color, vec3(0.0), metallic);
+                        return occlusion * ambientColor * material_ambient_factor * surface_diffuse(surface_color, metallic);
                     }}
                 
                 ", i)
@@ -90,10 +193,18 @@ impl Light for AmbientLight {
     }
     fn use_uniforms(&self, program: &Program, _i: u32) {
         if let Some(ref environment) = self.environment {
-            program.use_texture_cube("irradianceMap", &environment.irradiance_map);
+            if self.spherical_harmonics.is_none() {
+                program.use_texture_cube("irradianceMap", &environment.irradiance_map);
+            }
             program.use_texture_cube("prefilterMap", &environment.prefilter_map);
             program.use_texture("brdfLUT", &environment.brdf_map);
         }
+        if let Some(ref spherical_harmonics) = self.spherical_harmonics {
+            program.use_uniform_array(
+                "sphericalHarmonics",
+                &spherical_harmonics.convolved_coefficients(),
+            );
+        }
         program.use_uniform(
             "ambientColor",
             self.color.to_linear_srgb().truncate() * self.intensity,
@@ -101,16 +212,38 @@ impl Light for AmbientLight {
     }
 
     fn id(&self) -> LightId {
-        LightId::AmbientLight(self.environment.is_some())
+        if self.spherical_harmonics.is_some() {
+            LightId::SphericalHarmonicsAmbientLight(self.environment.is_some())
+        } else {
+            LightId::AmbientLight(self.environment.is_some())
+        }
     }
 }
 
+const SPHERICAL_HARMONICS_SOURCE: &str = "
+    uniform vec3 sphericalHarmonics[9];
+
+    vec3 spherical_harmonics_irradiance(vec3 n)
+    {
+        return sphericalHarmonics[0] * 0.282095
+            + sphericalHarmonics[1] * 0.488603 * n.y
+            + sphericalHarmonics[2] * 0.488603 * n.z
+            + sphericalHarmonics[3] * 0.488603 * n.x
+            + sphericalHarmonics[4] * 1.092548 * n.x * n.y
+            + sphericalHarmonics[5] * 1.092548 * n.y * n.z
+            + sphericalHarmonics[6] * 0.315392 * (3.0 * n.z * n.z - 1.0)
+            + sphericalHarmonics[7] * 1.092548 * n.x * n.z
+            + sphericalHarmonics[8] * 0.546274 * (n.x * n.x - n.y * n.y);
+    }
+";
+
 impl Default for AmbientLight {
     fn default() -> Self {
         Self {
             color: Srgba::WHITE,
             intensity: 1.0,
             environment: None,
+            spherical_harmonics: None,
         }
     }
 }
@@ -0,0 +1,198 @@
+use crate::core::*;
+use crate::renderer::light::*;
+use crate::renderer::*;
+
+/// The maximum number of lights of each type in [BufferedLights].
+pub const MAX_BUFFERED_LIGHTS: u32 = 64;
+
+const DIRECTIONAL_LIGHT_SIZE: u32 = 2;
+const POINT_LIGHT_SIZE: u32 = 3;
+const SPOT_LIGHT_SIZE: u32 = 4;
+
+///
+/// A set of directional, point and spot lights which are packed into a [UniformBuffer] instead of being sent as individual uniforms.
+/// The shader loops over the lights in the buffer, so it does not depend on the number of lights
+/// and the same [Program] is used when lights are added or removed using [BufferedLights::update].
+/// This makes it efficient to render scenes where the set of lights changes often.
+///
+/// The shadow maps and photometric profiles of the lights are ignored, so lights that should cast shadows or use a photometric profile must be given as individual lights instead.
+///
+pub struct BufferedLights {
+    buffer: UniformBuffer,
+}
+
+impl BufferedLights {
+    ///
+    /// Constructs a new set of buffered lights containing the given lights.
+    ///
+    /// # Panic
+    /// Will panic if there are more than [MAX_BUFFERED_LIGHTS] lights of any type.
+    ///
+    pub fn new<'a>(
+        context: &Context,
+        directional_lights: impl IntoIterator<Item = &'a DirectionalLight>,
+        point_lights: impl IntoIterator<Item = &'a PointLight>,
+        spot_lights: impl IntoIterator<Item = &'a SpotLight>,
+    ) -> Self {
+        let mut lights = Self {
+            buffer: UniformBuffer::new(
+                context,
+                &[
+                    4,
+                    4 * DIRECTIONAL_LIGHT_SIZE * MAX_BUFFERED_LIGHTS,
+                    4 * POINT_LIGHT_SIZE * MAX_BUFFERED_LIGHTS,
+                    4 * SPOT_LIGHT_SIZE * MAX_BUFFERED_LIGHTS,
+                ],
+            ),
+        };
+        lights.update(directional_lights, point_lights, spot_lights);
+        lights
+    }
+
+    ///
+    /// Replaces the lights in the buffer with the given lights.
+    /// This should also be called when any of the lights have changed.
+    ///
+    /// # Panic
+    /// Will panic if there are more than [MAX_BUFFERED_LIGHTS] lights of any type.
+    ///
+    pub fn update<'a>(
+        &mut self,
+        directional_lights: impl IntoIterator<Item = &'a DirectionalLight>,
+        point_lights: impl IntoIterator<Item = &'a PointLight>,
+        spot_lights: impl IntoIterator<Item = &'a SpotLight>,
+    ) {
+        let directional_data = directional_lights
+            .into_iter()
+            .flat_map(|light| {
+                [
+                    color(light.color, light.intensity).extend(0.0),
+                    light.direction.normalize().extend(0.0),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let point_data = point_lights
+            .into_iter()
+            .flat_map(|light| {
+                [
+                    color(light.color, light.intensity).extend(0.0),
+                    light.position.extend(0.0),
+                    attenuation(light.attenuation),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let spot_data = spot_lights
+            .into_iter()
+            .flat_map(|light| {
+                [
+                    color(light.color, light.intensity).extend(0.0),
+                    light.position.extend(light.cutoff.0),
+                    light.direction.normalize().extend(0.0),
+                    attenuation(light.attenuation),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let counts = [
+            directional_data.len() as u32 / DIRECTIONAL_LIGHT_SIZE,
+            point_data.len() as u32 / POINT_LIGHT_SIZE,
+            spot_data.len() as u32 / SPOT_LIGHT_SIZE,
+        ];
+        if counts.iter().any(|count| *count > MAX_BUFFERED_LIGHTS) {
+            panic!(
+                "buffered lights contain {} directional, {} point and {} spot lights but at most {} of each type is supported",
+                counts[0], counts[1], counts[2], MAX_BUFFERED_LIGHTS
+            );
+        }
+        self.buffer.update(
+            0,
+            &[counts[0] as f32, counts[1] as f32, counts[2] as f32, 0.0],
+        );
+        for (index, (data, size)) in [
+            (directional_data, DIRECTIONAL_LIGHT_SIZE),
+            (point_data, POINT_LIGHT_SIZE),
+            (spot_data, SPOT_LIGHT_SIZE),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut values = vec![0.0; (4 * size * MAX_BUFFERED_LIGHTS) as usize];
+            for (i, v) in data.iter().enumerate() {
+                values[4 * i..4 * i + 4].copy_from_slice(&[v.x, v.y, v.z, v.w]);
+            }
+            self.buffer.update(index as u32 + 1, &values);
+        }
+    }
+}
+
+fn color(color: Srgba, intensity: f32) -> Vec3 {
+    color.to_linear_srgb().truncate() * intensity
+}
+
+fn attenuation(attenuation: Attenuation) -> Vec4 {
+    vec4(
+        attenuation.constant,
+        attenuation.linear,
+        attenuation.quadratic,
+        0.0,
+    )
+}
+
+impl Light for BufferedLights {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+        "
+            layout (std140) uniform BufferedLights{}
+            {{
+                vec4 counts;
+                vec4 directional[{}];
+                vec4 point[{}];
+                vec4 spot[{}];
+            }} bufferedLights{};
+
+            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+            {{
+                vec3 color = vec3(0.0);
+                for (int l = 0; l < int(bufferedLights{}.counts.x); l++) {{
+                    vec3 light_color = bufferedLights{}.directional[{} * l].xyz;
+                    vec3 light_direction = -bufferedLights{}.directional[{} * l + 1].xyz;
+                    color += calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
+                }}
+                for (int l = 0; l < int(bufferedLights{}.counts.y); l++) {{
+                    vec3 light_direction = bufferedLights{}.point[{} * l + 1].xyz - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+                    vec3 light_color = attenuate(bufferedLights{}.point[{} * l].xyz, bufferedLights{}.point[{} * l + 2].xyz, distance);
+                    color += calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
+                }}
+                for (int l = 0; l < int(bufferedLights{}.counts.z); l++) {{
+                    vec4 light_position = bufferedLights{}.spot[{} * l + 1];
+                    vec3 light_direction = light_position.xyz - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+                    float angle = acos(dot(-light_direction, bufferedLights{}.spot[{} * l + 2].xyz));
+                    float cutoff = light_position.w;
+                    if (angle < cutoff) {{
+                        vec3 light_color = attenuate(bufferedLights{}.spot[{} * l].xyz, bufferedLights{}.spot[{} * l + 3].xyz, distance);
+                        color += calculate_light(light_color, light_direction, surface_color, view_direction, normal,
+                            metallic, roughness) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
+                    }}
+                }}
+                return color;
+            }}
+
+        ", i,
+        DIRECTIONAL_LIGHT_SIZE * MAX_BUFFERED_LIGHTS, POINT_LIGHT_SIZE * MAX_BUFFERED_LIGHTS, SPOT_LIGHT_SIZE * MAX_BUFFERED_LIGHTS,
+        i, i,
+        i, i, DIRECTIONAL_LIGHT_SIZE, i, DIRECTIONAL_LIGHT_SIZE,
+        i, i, POINT_LIGHT_SIZE, i, POINT_LIGHT_SIZE, i, POINT_LIGHT_SIZE,
+        i, i, SPOT_LIGHT_SIZE, i, SPOT_LIGHT_SIZE, i, SPOT_LIGHT_SIZE, i, SPOT_LIGHT_SIZE)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        program.use_uniform_block(&format!("BufferedLights{}", i), &self.buffer);
+    }
+
+    fn id(&self) -> LightId {
+        LightId::BufferedLights
+    }
+}
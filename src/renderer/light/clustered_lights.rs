@@ -0,0 +1,364 @@
+use crate::core::*;
+use crate::renderer::light::*;
+use crate::renderer::*;
+
+const TILES_X: u32 = 16;
+const TILES_Y: u32 = 9;
+const SLICES: u32 = 24;
+const INDEX_TEXTURE_WIDTH: u32 = 1024;
+
+///
+/// A set of point and spot lights which are culled using clustered forward shading.
+/// The view frustum of a viewer is divided into a grid of clusters (16 x 9 tiles on the screen and 24 exponentially distributed depth slices)
+/// and each light is assigned to the clusters that are within its [range](PointLight::range).
+/// When shading a fragment, only the lights assigned to the cluster containing the fragment are evaluated,
+/// which makes it possible to render hundreds of lights as long as each light only affects a small part of the scene.
+/// The shader does not depend on the number of lights, so the same [Program] is used when lights are added or removed.
+///
+/// The light assignment depends on the viewer, so [ClusteredLights::update] must be called whenever the viewer or the lights change.
+/// The shadow maps and photometric profiles of the lights are ignored and lights with an infinite range, which is the case for the default [Attenuation], are assigned to all clusters.
+///
+pub struct ClusteredLights {
+    context: Context,
+    light_texture: Texture2D,
+    cluster_texture: Texture2D,
+    index_texture: Texture2D,
+    view: Mat4,
+    viewport: Viewport,
+    depth_range: Vec2,
+}
+
+impl ClusteredLights {
+    ///
+    /// Constructs a new set of clustered lights containing the given lights and assigns them to the clusters of the given viewer.
+    ///
+    pub fn new<'a>(
+        context: &Context,
+        viewer: impl Viewer,
+        point_lights: impl IntoIterator<Item = &'a PointLight>,
+        spot_lights: impl IntoIterator<Item = &'a SpotLight>,
+    ) -> Self {
+        let mut lights = Self {
+            context: context.clone(),
+            light_texture: data_texture::<[f32; 4]>(context, 4, 1),
+            cluster_texture: data_texture::<[f32; 2]>(context, TILES_X * TILES_Y, SLICES),
+            index_texture: data_texture::<f32>(context, INDEX_TEXTURE_WIDTH, 1),
+            view: Mat4::identity(),
+            viewport: Viewport::new_at_origo(1, 1),
+            depth_range: vec2(1.0, 2.0),
+        };
+        lights.update(viewer, point_lights, spot_lights);
+        lights
+    }
+
+    ///
+    /// Replaces the lights with the given lights and assigns them to the clusters of the given viewer.
+    /// This should be called whenever the viewer or any of the lights have changed.
+    ///
+    pub fn update<'a>(
+        &mut self,
+        viewer: impl Viewer,
+        point_lights: impl IntoIterator<Item = &'a PointLight>,
+        spot_lights: impl IntoIterator<Item = &'a SpotLight>,
+    ) {
+        // Light data and bounding spheres
+        let mut light_data = Vec::new();
+        let mut spheres = Vec::new();
+        for light in point_lights {
+            let color = light.color.to_linear_srgb().truncate() * light.intensity;
+            light_data.extend([
+                color.extend(0.0),
+                light.position.extend(0.0),
+                Vec4::zero(),
+                attenuation(light.attenuation),
+            ]);
+            spheres.push((light.position, light.range()));
+        }
+        for light in spot_lights {
+            let color = light.color.to_linear_srgb().truncate() * light.intensity;
+            let direction = light.direction.normalize();
+            light_data.extend([
+                color.extend(1.0),
+                light.position.extend(light.cutoff.0),
+                direction.extend(0.0),
+                attenuation(light.attenuation),
+            ]);
+            spheres.push(cone_bounding_sphere(
+                light.position,
+                direction,
+                light.cutoff,
+                light.range(),
+            ));
+        }
+        let light_count = spheres.len() as u32;
+
+        // Depth slices
+        let z_near = viewer.z_near();
+        let z_far = viewer.z_far();
+        let slice_near = if z_near > 0.0 { z_near } else { 0.001 * z_far };
+        let slice_depth = |slice: u32| match slice {
+            0 => z_near,
+            SLICES => z_far,
+            _ => slice_near * (z_far / slice_near).powf(slice as f32 / SLICES as f32),
+        };
+
+        // Bounding boxes of the clusters in view space
+        let projection_inverse = viewer.projection().invert().unwrap();
+        let unproject = |x: f32, y: f32, z: f32| {
+            let p = projection_inverse * vec4(x, y, z, 1.0);
+            p.truncate() / p.w
+        };
+        let mut cluster_boxes = Vec::with_capacity((TILES_X * TILES_Y * SLICES) as usize);
+        for slice in 0..SLICES {
+            let depths = [slice_depth(slice), slice_depth(slice + 1)];
+            for y in 0..TILES_Y {
+                for x in 0..TILES_X {
+                    let mut aabb = AxisAlignedBoundingBox::EMPTY;
+                    for (tx, ty) in [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)] {
+                        let ndc_x = 2.0 * tx as f32 / TILES_X as f32 - 1.0;
+                        let ndc_y = 2.0 * ty as f32 / TILES_Y as f32 - 1.0;
+                        let near = unproject(ndc_x, ndc_y, -1.0);
+                        let far = unproject(ndc_x, ndc_y, 1.0);
+                        let points = depths.map(|depth| {
+                            near + (far - near) * ((depth + near.z) / (near.z - far.z))
+                        });
+                        aabb.expand(&points);
+                    }
+                    cluster_boxes.push(aabb);
+                }
+            }
+        }
+
+        // Assign the lights to the clusters
+        let view = viewer.view();
+        let projection = viewer.projection();
+        let mut clusters = vec![Vec::new(); cluster_boxes.len()];
+        for (index, (center, radius)) in spheres.into_iter().enumerate() {
+            if radius <= 0.0 {
+                continue;
+            }
+            let center = (view * center.extend(1.0)).truncate();
+            let depth = -center.z;
+            if depth + radius < z_near || depth - radius > z_far {
+                continue;
+            }
+            let slices = (0..SLICES)
+                .filter(|slice| {
+                    slice_depth(slice + 1) >= depth - radius
+                        && slice_depth(*slice) <= depth + radius
+                })
+                .collect::<Vec<_>>();
+            let (min_tile, max_tile) = screen_bounds(projection, center, radius);
+            let tiles_x = (min_tile.x * TILES_X as f32).floor().max(0.0) as u32
+                ..((max_tile.x * TILES_X as f32).ceil() as u32).min(TILES_X);
+            let tiles_y = (min_tile.y * TILES_Y as f32).floor().max(0.0) as u32
+                ..((max_tile.y * TILES_Y as f32).ceil() as u32).min(TILES_Y);
+            for slice in slices {
+                for y in tiles_y.clone() {
+                    for x in tiles_x.clone() {
+                        let cluster = (x + y * TILES_X + slice * TILES_X * TILES_Y) as usize;
+                        if cluster_boxes[cluster].distance(center) <= radius {
+                            clusters[cluster].push(index as f32);
+                        }
+                    }
+                }
+            }
+        }
+        let mut indices = Vec::new();
+        let cluster_data = clusters
+            .into_iter()
+            .map(|cluster| {
+                let offset = indices.len() as f32;
+                indices.extend(cluster);
+                [offset, indices.len() as f32 - offset]
+            })
+            .collect::<Vec<_>>();
+        let index_rows = (indices.len() as u32).div_ceil(INDEX_TEXTURE_WIDTH).max(1);
+        indices.resize((index_rows * INDEX_TEXTURE_WIDTH) as usize, 0.0);
+
+        if light_count > 0 {
+            update_data_texture(
+                &self.context,
+                &mut self.light_texture,
+                4,
+                light_count,
+                &light_data
+                    .iter()
+                    .map(|v| [v.x, v.y, v.z, v.w])
+                    .collect::<Vec<_>>(),
+            );
+        }
+        update_data_texture(
+            &self.context,
+            &mut self.cluster_texture,
+            TILES_X * TILES_Y,
+            SLICES,
+            &cluster_data,
+        );
+        update_data_texture(
+            &self.context,
+            &mut self.index_texture,
+            INDEX_TEXTURE_WIDTH,
+            index_rows,
+            &indices,
+        );
+        self.view = view;
+        self.viewport = viewer.viewport();
+        self.depth_range = vec2(slice_near, z_far);
+    }
+}
+
+fn attenuation(attenuation: Attenuation) -> Vec4 {
+    vec4(
+        attenuation.constant,
+        attenuation.linear,
+        attenuation.quadratic,
+        0.0,
+    )
+}
+
+///
+/// Returns the center and radius of a sphere which contains the cone with the given apex, direction, half angle and length.
+///
+fn cone_bounding_sphere(
+    position: Vec3,
+    direction: Vec3,
+    cutoff: Radians,
+    range: f32,
+) -> (Vec3, f32) {
+    if range.is_infinite() {
+        (position, range)
+    } else if cutoff.0 > std::f32::consts::FRAC_PI_4 {
+        (
+            position + direction * cutoff.0.cos() * range,
+            cutoff.0.sin() * range,
+        )
+    } else {
+        let radius = range / (2.0 * cutoff.0.cos());
+        (position + direction * radius, radius)
+    }
+}
+
+///
+/// Returns the bounds of the given sphere in view space projected onto the screen in the range `[0..1]`.
+///
+fn screen_bounds(projection: Mat4, center: Vec3, radius: f32) -> (Vec2, Vec2) {
+    let mut min = vec2(f32::MAX, f32::MAX);
+    let mut max = vec2(f32::MIN, f32::MIN);
+    for x in [-radius, radius] {
+        for y in [-radius, radius] {
+            for z in [-radius, radius] {
+                let p = projection * (center + vec3(x, y, z)).extend(1.0);
+                if p.w <= 0.0001 || !radius.is_finite() {
+                    // The sphere intersects the plane of the viewer, so it potentially covers the entire screen
+                    return (vec2(0.0, 0.0), vec2(1.0, 1.0));
+                }
+                let p = vec2(p.x, p.y) / p.w * 0.5 + vec2(0.5, 0.5);
+                min = vec2(min.x.min(p.x), min.y.min(p.y));
+                max = vec2(max.x.max(p.x), max.y.max(p.y));
+            }
+        }
+    }
+    (min, max)
+}
+
+fn data_texture<T: TextureDataType>(context: &Context, width: u32, height: u32) -> Texture2D {
+    Texture2D::new_empty::<T>(
+        context,
+        width,
+        height,
+        Interpolation::Nearest,
+        Interpolation::Nearest,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    )
+}
+
+fn update_data_texture<T: TextureDataType>(
+    context: &Context,
+    texture: &mut Texture2D,
+    width: u32,
+    height: u32,
+    data: &[T],
+) {
+    if texture.width() != width || texture.height() != height {
+        *texture = data_texture::<T>(context, width, height);
+    }
+    // The first row of the data is accessed as row 0 in the shader which is the bottom of the texture
+    texture.fill(&flip_rows(data, width as usize));
+}
+
+impl Light for ClusteredLights {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+        "
+            uniform sampler2D clusterLights{};
+            uniform sampler2D clusters{};
+            uniform sampler2D clusterIndices{};
+            uniform mat4 clusterView{};
+            uniform vec4 clusterViewport{};
+            uniform vec2 clusterDepthRange{};
+
+            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+            {{
+                const vec3 size = vec3({}.0, {}.0, {}.0);
+                vec2 tile = clamp(floor((gl_FragCoord.xy - clusterViewport{}.xy) / clusterViewport{}.zw * size.xy), vec2(0.0), size.xy - 1.0);
+                float depth = max(-(clusterView{} * vec4(position, 1.0)).z, clusterDepthRange{}.x);
+                float slice = clamp(floor(log(depth / clusterDepthRange{}.x) / log(clusterDepthRange{}.y / clusterDepthRange{}.x) * size.z), 0.0, size.z - 1.0);
+                vec2 cluster = texelFetch(clusters{}, ivec2(int(tile.x + tile.y * size.x), int(slice)), 0).xy;
+
+                vec3 color = vec3(0.0);
+                int offset = int(cluster.x);
+                int count = int(cluster.y);
+                for (int l = 0; l < count; l++) {{
+                    int index = offset + l;
+                    int light = int(texelFetch(clusterIndices{}, ivec2(index % {}, index / {}), 0).x);
+                    vec4 light_color = texelFetch(clusterLights{}, ivec2(0, light), 0);
+                    vec4 light_position = texelFetch(clusterLights{}, ivec2(1, light), 0);
+                    vec3 light_direction = light_position.xyz - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    float cone = 1.0;
+                    if (light_color.w > 0.5) {{
+                        float angle = acos(dot(-light_direction, texelFetch(clusterLights{}, ivec2(2, light), 0).xyz));
+                        float cutoff = light_position.w;
+                        cone = 1.0 - smoothstep(0.75 * cutoff, cutoff, angle);
+                    }}
+                    if (cone > 0.0) {{
+                        vec3 attenuated_color = attenuate(light_color.rgb, texelFetch(clusterLights{}, ivec2(3, light), 0).xyz, distance);
+                        color += calculate_light(attenuated_color, light_direction, surface_color, view_direction, normal,
+                            metallic, roughness) * cone;
+                    }}
+                }}
+                return color;
+            }}
+
+        ", i, i, i, i, i, i, i,
+        TILES_X, TILES_Y, SLICES,
+        i, i, i, i, i, i, i, i,
+        i, INDEX_TEXTURE_WIDTH, INDEX_TEXTURE_WIDTH, i, i, i, i)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        program.use_texture(&format!("clusterLights{}", i), &self.light_texture);
+        program.use_texture(&format!("clusters{}", i), &self.cluster_texture);
+        program.use_texture(&format!("clusterIndices{}", i), &self.index_texture);
+        program.use_uniform(&format!("clusterView{}", i), self.view);
+        program.use_uniform(
+            &format!("clusterViewport{}", i),
+            vec4(
+                self.viewport.x as f32,
+                self.viewport.y as f32,
+                self.viewport.width as f32,
+                self.viewport.height as f32,
+            ),
+        );
+        program.use_uniform(&format!("clusterDepthRange{}", i), self.depth_range);
+    }
+
+    fn id(&self) -> LightId {
+        LightId::ClusteredLights
+    }
+}
@@ -0,0 +1,386 @@
+//!
+//! Generates the lookup tables `ltc_matrix.bin` and `ltc_amplitude.bin` used by the area lights (see `LtcTables` in `light.rs`).
+//! This is a standalone program without dependencies which is not part of the crate, run it from this directory using
+//! `rustc -O ltc_fit.rs && ./ltc_fit`, it takes a minute or two.
+//!
+//! It is an implementation of the fitting procedure described in
+//! 'Real-Time Polygonal-Light Shading with Linearly Transformed Cosines' by Heitz, Dupuy, Hill and Neubelt (2016)
+//! with the following parameters:
+//! - The tables are 64x64 and indexed by the roughness (u) and `sqrt(1 - cos(theta))` (v), where theta is the angle between the normal and the view direction, capped at 1.57 radians.
+//! - The GGX distribution uses `alpha = roughness * roughness`, but at least 0.00001, and the height correlated Smith masking-shadowing function.
+//! - The error of a fit is estimated using 32x32 stratified samples of both the LTC and the GGX distribution.
+//! - Each fit is found using Nelder-Mead with an initial step of 0.05, a relative tolerance of 0.00001 and at most 100 iterations,
+//!   starting from the fit of the neighbouring entry, beginning at roughness 1 and normal incidence.
+//! - The matrix table contains the entries `m00`, `m20`, `m02` and `m22` of the inverse LTC matrix normalized by `m11`,
+//!   and the amplitude table contains the norm of the BRDF and the part of it scaled by the Schlick fresnel term.
+//! - All values are stored as little-endian 32 bit floats.
+//!
+use std::f64::consts::PI;
+
+type V3 = [f64; 3];
+type M3 = [[f64; 3]; 3]; // row major
+
+fn dot(a: V3, b: V3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+fn len(a: V3) -> f64 {
+    dot(a, a).sqrt()
+}
+fn norm(a: V3) -> V3 {
+    let l = len(a);
+    [a[0] / l, a[1] / l, a[2] / l]
+}
+fn add(a: V3, b: V3) -> V3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+fn scale(a: V3, s: f64) -> V3 {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+fn mul(m: &M3, v: V3) -> V3 {
+    [dot(m[0], v), dot(m[1], v), dot(m[2], v)]
+}
+fn mmul(a: &M3, b: &M3) -> M3 {
+    let mut r = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            for k in 0..3 {
+                r[i][j] += a[i][k] * b[k][j];
+            }
+        }
+    }
+    r
+}
+fn det(m: &M3) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+fn inv(m: &M3) -> M3 {
+    let d = det(m);
+    let mut r = [[0.0; 3]; 3];
+    r[0][0] = (m[1][1] * m[2][2] - m[1][2] * m[2][1]) / d;
+    r[0][1] = (m[0][2] * m[2][1] - m[0][1] * m[2][2]) / d;
+    r[0][2] = (m[0][1] * m[1][2] - m[0][2] * m[1][1]) / d;
+    r[1][0] = (m[1][2] * m[2][0] - m[1][0] * m[2][2]) / d;
+    r[1][1] = (m[0][0] * m[2][2] - m[0][2] * m[2][0]) / d;
+    r[1][2] = (m[0][2] * m[1][0] - m[0][0] * m[1][2]) / d;
+    r[2][0] = (m[1][0] * m[2][1] - m[1][1] * m[2][0]) / d;
+    r[2][1] = (m[0][1] * m[2][0] - m[0][0] * m[2][1]) / d;
+    r[2][2] = (m[0][0] * m[1][1] - m[0][1] * m[1][0]) / d;
+    r
+}
+
+fn lambda(alpha: f64, cos_theta: f64) -> f64 {
+    let a = 1.0 / alpha / (1.0 - cos_theta * cos_theta).max(1e-12).sqrt() * cos_theta;
+    (-1.0 + (1.0 + 1.0 / (a * a)).sqrt()) / 2.0
+}
+
+// returns (value including cosine, pdf)
+fn brdf_eval(v: V3, l: V3, alpha: f64) -> (f64, f64) {
+    if v[2] <= 0.0 || l[2] <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let lv = lambda(alpha, v[2]);
+    let ll = lambda(alpha, l[2]);
+    let g2 = 1.0 / (1.0 + lv + ll);
+    let h = norm(add(v, l));
+    let sx = h[0] / h[2];
+    let sy = h[1] / h[2];
+    let mut d = 1.0 / (1.0 + (sx * sx + sy * sy) / alpha / alpha);
+    d = d * d;
+    d /= PI * alpha * alpha * h[2].powi(4);
+    let pdf = (d * h[2] / 4.0 / dot(v, h)).abs();
+    (d * g2 / 4.0 / v[2], pdf)
+}
+
+fn brdf_sample(v: V3, alpha: f64, u1: f64, u2: f64) -> V3 {
+    let phi = 2.0 * PI * u1;
+    let r = alpha * (u2 / (1.0 - u2)).sqrt();
+    let n = norm([r * phi.cos(), r * phi.sin(), 1.0]);
+    add(scale(v, -1.0), scale(n, 2.0 * dot(n, v)))
+}
+
+#[derive(Clone, Copy)]
+struct Ltc {
+    m11: f64,
+    m22: f64,
+    m13: f64,
+    x: V3,
+    y: V3,
+    z: V3,
+    magnitude: f64,
+    m: M3,
+    inv_m: M3,
+    det_m: f64,
+}
+impl Ltc {
+    fn update(&mut self) {
+        let frame = [
+            [self.x[0], self.y[0], self.z[0]],
+            [self.x[1], self.y[1], self.z[1]],
+            [self.x[2], self.y[2], self.z[2]],
+        ];
+        let s = [
+            [self.m11, 0.0, self.m13],
+            [0.0, self.m22, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        self.m = mmul(&frame, &s);
+        self.inv_m = inv(&self.m);
+        self.det_m = det(&self.m).abs();
+    }
+    fn eval(&self, l: V3) -> f64 {
+        let lo = norm(mul(&self.inv_m, l));
+        let l_ = mul(&self.m, lo);
+        let ll = len(l_);
+        let jac = self.det_m / (ll * ll * ll);
+        let d = lo[2].max(0.0) / PI;
+        self.magnitude * d / jac
+    }
+    fn sample(&self, u1: f64, u2: f64) -> V3 {
+        let theta = u1.sqrt().acos();
+        let phi = 2.0 * PI * u2;
+        norm(mul(
+            &self.m,
+            [
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            ],
+        ))
+    }
+}
+
+const NS: usize = 32;
+
+fn compute_error(ltc: &Ltc, v: V3, alpha: f64) -> f64 {
+    let mut error = 0.0;
+    for j in 0..NS {
+        for i in 0..NS {
+            let u1 = (i as f64 + 0.5) / NS as f64;
+            let u2 = (j as f64 + 0.5) / NS as f64;
+            {
+                let l = ltc.sample(u1, u2);
+                let (eb, pb) = brdf_eval(v, l, alpha);
+                let el = ltc.eval(l);
+                let pl = el / ltc.magnitude;
+                let e = (eb - el).abs();
+                if pl + pb > 0.0 {
+                    error += e * e * e / (pl + pb);
+                }
+            }
+            {
+                let l = brdf_sample(v, alpha, u1, u2);
+                let (eb, pb) = brdf_eval(v, l, alpha);
+                let el = ltc.eval(l);
+                let pl = el / ltc.magnitude;
+                let e = (eb - el).abs();
+                if pl + pb > 0.0 {
+                    error += e * e * e / (pl + pb);
+                }
+            }
+        }
+    }
+    error / (NS * NS) as f64
+}
+
+fn avg_terms(v: V3, alpha: f64) -> (f64, f64, V3) {
+    let mut n = 0.0;
+    let mut f = 0.0;
+    let mut dir = [0.0; 3];
+    for j in 0..NS {
+        for i in 0..NS {
+            let u1 = (i as f64 + 0.5) / NS as f64;
+            let u2 = (j as f64 + 0.5) / NS as f64;
+            let l = brdf_sample(v, alpha, u1, u2);
+            let (e, p) = brdf_eval(v, l, alpha);
+            if p > 0.0 {
+                let w = e / p;
+                let h = norm(add(v, l));
+                n += w;
+                f += w * (1.0 - dot(v, h).max(0.0)).powi(5);
+                dir = add(dir, scale(l, w));
+            }
+        }
+    }
+    n /= (NS * NS) as f64;
+    f /= (NS * NS) as f64;
+    dir[1] = 0.0;
+    (n, f, norm(dir))
+}
+
+fn nelder_mead(
+    f: &dyn Fn(&[f64; 3]) -> f64,
+    start: [f64; 3],
+    delta: f64,
+    tol: f64,
+    max_iters: usize,
+) -> [f64; 3] {
+    let mut s: Vec<[f64; 3]> = vec![start; 4];
+    for i in 0..3 {
+        s[i + 1][i] += delta;
+    }
+    let mut fv: Vec<f64> = s.iter().map(f).collect();
+    for _ in 0..max_iters {
+        let mut idx: Vec<usize> = (0..4).collect();
+        idx.sort_by(|&a, &b| fv[a].total_cmp(&fv[b]));
+        let (lo, hi, nh) = (idx[0], idx[3], idx[2]);
+        if (fv[hi] - fv[lo]).abs() / (fv[hi].abs() + fv[lo].abs() + 1e-20) < tol {
+            break;
+        }
+        let mut c = [0.0; 3];
+        for &k in &idx[0..3] {
+            for d in 0..3 {
+                c[d] += s[k][d] / 3.0;
+            }
+        }
+        let lerp = |t: f64| {
+            let mut p = [0.0; 3];
+            for d in 0..3 {
+                p[d] = c[d] + t * (s[hi][d] - c[d]);
+            }
+            p
+        };
+        let r = lerp(-1.0);
+        let fr = f(&r);
+        if fr < fv[lo] {
+            let e = lerp(-2.0);
+            let fe = f(&e);
+            if fe < fr {
+                s[hi] = e;
+                fv[hi] = fe;
+            } else {
+                s[hi] = r;
+                fv[hi] = fr;
+            }
+        } else if fr < fv[nh] {
+            s[hi] = r;
+            fv[hi] = fr;
+        } else {
+            let (cp, fcp) = if fr < fv[hi] {
+                let p = lerp(-0.5);
+                let v = f(&p);
+                (p, v)
+            } else {
+                let p = lerp(0.5);
+                let v = f(&p);
+                (p, v)
+            };
+            if fcp < fv[hi].min(fr) {
+                s[hi] = cp;
+                fv[hi] = fcp;
+            } else {
+                for &k in &idx[1..4] {
+                    for d in 0..3 {
+                        s[k][d] = s[lo][d] + 0.5 * (s[k][d] - s[lo][d]);
+                    }
+                    fv[k] = f(&s[k]);
+                }
+            }
+        }
+    }
+    let mut best = 0;
+    for k in 1..4 {
+        if fv[k] < fv[best] {
+            best = k;
+        }
+    }
+    s[best]
+}
+
+const N: usize = 64;
+const MIN_ALPHA: f64 = 0.00001;
+
+fn main() {
+    let mut tab: Vec<M3> = vec![[[0.0; 3]; 3]; N * N];
+    let mut mag: Vec<[f64; 2]> = vec![[0.0; 2]; N * N];
+    for a in (0..N).rev() {
+        let mut ltc = Ltc {
+            m11: 1.0,
+            m22: 1.0,
+            m13: 0.0,
+            x: [1.0, 0.0, 0.0],
+            y: [0.0, 1.0, 0.0],
+            z: [0.0, 0.0, 1.0],
+            magnitude: 1.0,
+            m: [[0.0; 3]; 3],
+            inv_m: [[0.0; 3]; 3],
+            det_m: 1.0,
+        };
+        for t in 0..N {
+            let x = t as f64 / (N - 1) as f64;
+            let ct = 1.0 - x * x;
+            let theta = ct.acos().min(1.57);
+            let v = [theta.sin(), 0.0, theta.cos()];
+            let roughness = a as f64 / (N - 1) as f64;
+            let alpha = (roughness * roughness).max(MIN_ALPHA);
+            let (n, f, avg) = avg_terms(v, alpha);
+            ltc.magnitude = n;
+            let isotropic;
+            if t == 0 {
+                ltc.x = [1.0, 0.0, 0.0];
+                ltc.y = [0.0, 1.0, 0.0];
+                ltc.z = [0.0, 0.0, 1.0];
+                if a == N - 1 {
+                    ltc.m11 = 1.0;
+                    ltc.m22 = 1.0;
+                } else {
+                    ltc.m11 = tab[a + 1][0][0];
+                    ltc.m22 = tab[a + 1][1][1];
+                }
+                ltc.m13 = 0.0;
+                ltc.update();
+                isotropic = true;
+            } else {
+                ltc.x = [avg[2], 0.0, -avg[0]];
+                ltc.y = [0.0, 1.0, 0.0];
+                ltc.z = avg;
+                ltc.update();
+                isotropic = false;
+            }
+            let base = ltc;
+            let set = |p: &[f64; 3]| {
+                let mut l = base;
+                l.m11 = p[0].max(MIN_ALPHA);
+                l.m22 = p[1].max(MIN_ALPHA);
+                l.m13 = p[2];
+                if isotropic {
+                    l.m22 = l.m11;
+                    l.m13 = 0.0;
+                }
+                l.update();
+                l
+            };
+            let err = |p: &[f64; 3]| compute_error(&set(p), v, alpha);
+            let best = nelder_mead(&err, [ltc.m11, ltc.m22, ltc.m13], 0.05, 1e-5, 100);
+            ltc = set(&best);
+            let mut m = ltc.m;
+            m[0][1] = 0.0;
+            m[1][0] = 0.0;
+            m[2][1] = 0.0;
+            m[1][2] = 0.0;
+            tab[a + t * N] = m;
+            mag[a + t * N] = [n, f];
+        }
+        eprintln!("roughness row {} done", a);
+    }
+    let mut out1 = Vec::new();
+    let mut out2 = Vec::new();
+    for i in 0..N * N {
+        let mut im = inv(&tab[i]);
+        let s = im[1][1];
+        for r in 0..3 {
+            for c in 0..3 {
+                im[r][c] /= s;
+            }
+        }
+        for v in [im[0][0], im[2][0], im[0][2], im[2][2]] {
+            out1.extend_from_slice(&(v as f32).to_le_bytes());
+        }
+        for v in mag[i] {
+            out2.extend_from_slice(&(v as f32).to_le_bytes());
+        }
+    }
+    std::fs::write("ltc_matrix.bin", out1).unwrap();
+    std::fs::write("ltc_amplitude.bin", out2).unwrap();
+}
@@ -2,20 +2,35 @@ use crate::core::*;
 use crate::renderer::light::*;
 use crate::renderer::*;
 
+/// The maximum number of cascades supported by [DirectionalLight::generate_cascaded_shadow_map].
+pub const MAX_SHADOW_CASCADES: u32 = 4;
+
 ///
 /// A light which shines in the given direction.
-/// The light will cast shadows if you [generate a shadow map](DirectionalLight::generate_shadow_map).
+/// The light will cast shadows if you [generate a shadow map](DirectionalLight::generate_shadow_map),
+/// [update a cached shadow map](DirectionalLight::update_shadow_map) or [generate a cascaded shadow map](DirectionalLight::generate_cascaded_shadow_map).
+/// Transparent objects cast colored shadows if you [generate a shadow map with transmittance](DirectionalLight::generate_shadow_map_with_transmittance).
 ///
 pub struct DirectionalLight {
     context: Context,
     shadow_texture: Option<DepthTexture2D>,
     shadow_matrix: Mat4,
+    shadow_cache: ShadowCache,
+    transmittance_map: Option<TransmittanceMap>,
+    cascaded_shadow_texture: Option<DepthTexture2DArray>,
+    cascade_matrices: [Mat4; MAX_SHADOW_CASCADES as usize],
+    cascade_splits: Vec4,
+    cascade_view: Mat4,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
     pub color: Srgba,
     /// The direction the light shines.
     pub direction: Vec3,
+    /// The filter used when sampling the shadow map which determines how soft the edges of the shadows are.
+    pub shadow_filter: ShadowFilter,
+    /// The [ShadowBias] used when rendering and sampling the shadow map.
+    pub shadow_bias: ShadowBias,
 }
 
 impl DirectionalLight {
@@ -30,19 +45,34 @@ impl DirectionalLight {
             context: context.clone(),
             shadow_matrix: Mat4::identity(),
             shadow_texture: None,
+            shadow_cache: ShadowCache::default(),
+            transmittance_map: None,
+            cascaded_shadow_texture: None,
+            cascade_matrices: [Mat4::identity(); MAX_SHADOW_CASCADES as usize],
+            cascade_splits: Vec4::zero(),
+            cascade_view: Mat4::identity(),
             intensity,
             color,
             direction,
+            shadow_filter: ShadowFilter::default(),
+            shadow_bias: ShadowBias::default(),
         }
     }
 
     ///
     /// Clear the shadow map, effectively disable the shadow.
-    /// Only necessary if you want to disable the shadow, if you want to update the shadow, just use [DirectionalLight::generate_shadow_map].
+    /// Only necessary if you want to disable the shadow, if you want to update the shadow, just use [DirectionalLight::generate_shadow_map]
+    /// or [DirectionalLight::generate_cascaded_shadow_map].
     ///
     pub fn clear_shadow_map(&mut self) {
         self.shadow_texture = None;
         self.shadow_matrix = Mat4::identity();
+        self.shadow_cache = ShadowCache::default();
+        self.transmittance_map = None;
+        self.cascaded_shadow_texture = None;
+        self.cascade_matrices = [Mat4::identity(); MAX_SHADOW_CASCADES as usize];
+        self.cascade_splits = Vec4::zero();
+        self.cascade_view = Mat4::identity();
     }
 
     ///
@@ -50,28 +80,214 @@ impl DirectionalLight {
     /// It is recomended that the texture size is power of 2.
     /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size
     /// and/or split the scene by creating another light source with same parameters and let the two light sources shines on different parts of the scene.
+    /// The shadow texture is reused if it already exists and has the given size.
     ///
     pub fn generate_shadow_map(
         &mut self,
         texture_size: u32,
         geometries: impl IntoIterator<Item = impl Geometry> + Clone,
     ) {
-        let up = compute_up_direction(self.direction);
+        self.generate_shadow_map_internal(texture_size, geometries, geometry_depth_material);
+    }
 
-        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+    ///
+    /// Generate a shadow map in the same way as [DirectionalLight::generate_shadow_map], except that each object is rendered using its [depth material](Object::depth_material),
+    /// so for example the parts of an object with an [alpha cutout](PhysicalMaterial::alpha_cutout) that are cut away do not cast shadows.
+    ///
+    pub fn generate_shadow_map_with_objects(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        self.generate_shadow_map_internal(texture_size, objects, object_depth_material);
+    }
+
+    fn generate_shadow_map_internal<G: Geometry>(
+        &mut self,
+        texture_size: u32,
+        geometries: impl IntoIterator<Item = G> + Clone,
+        depth_material: impl Fn(&G) -> Option<DepthMaterial>,
+    ) {
         let mut aabb = AxisAlignedBoundingBox::EMPTY;
         for geometry in geometries.clone() {
             aabb.expand_with_aabb(geometry.aabb());
         }
+        if let Some(shadow_camera) = self.shadow_camera(texture_size, aabb) {
+            let mut shadow_texture =
+                reuse_shadow_texture(&self.context, self.shadow_texture.take(), texture_size);
+            render_shadow_map(
+                &self.context,
+                &shadow_camera,
+                shadow_texture.as_depth_target(),
+                geometries,
+                depth_material,
+                &self.shadow_bias,
+                None,
+            );
+            self.clear_shadow_map();
+            self.shadow_texture = Some(shadow_texture);
+            self.shadow_matrix = shadow_matrix(&shadow_camera);
+        }
+    }
+
+    ///
+    /// Generate a shadow map in the same way as [DirectionalLight::generate_shadow_map], except that objects with a [MaterialType::Transparent] material
+    /// which provides a [TransmittanceMaterial] are rendered into a transmittance map instead of the shadow map.
+    /// The light reaching a surface behind these objects is filtered by their color and alpha value,
+    /// so for example colored glass casts a colored shadow while the fully transparent parts of an alpha textured material, like foliage cards, cast no shadow.
+    /// The transmittance map is reused if it already exists and has the given size.
+    ///
+    pub fn generate_shadow_map_with_transmittance(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        let mut aabb = AxisAlignedBoundingBox::EMPTY;
+        for object in objects.clone() {
+            aabb.expand_with_aabb(object.aabb());
+        }
+        if let Some(shadow_camera) = self.shadow_camera(texture_size, aabb) {
+            let mut shadow_texture =
+                reuse_shadow_texture(&self.context, self.shadow_texture.take(), texture_size);
+            render_shadow_map(
+                &self.context,
+                &shadow_camera,
+                shadow_texture.as_depth_target(),
+                objects
+                    .clone()
+                    .into_iter()
+                    .filter(|o| transmittance_material(o).is_none()),
+                object_depth_material,
+                &self.shadow_bias,
+                None,
+            );
+            let transmittance_map = TransmittanceMap::render(
+                &self.context,
+                &shadow_camera,
+                self.transmittance_map.take(),
+                objects,
+                &self.shadow_bias,
+            );
+            self.clear_shadow_map();
+            self.shadow_texture = Some(shadow_texture);
+            self.shadow_matrix = shadow_matrix(&shadow_camera);
+            self.transmittance_map = transmittance_map;
+        }
+    }
+
+    ///
+    /// Updates the shadow map in the same way as [DirectionalLight::generate_shadow_map], but only if something has changed since the last update.
+    /// The shadow casters are split into static geometries, which rarely change, and dynamic geometries, for example animated characters.
+    /// The static geometries are rendered into a separate cached shadow map which is copied into the shadow map before the dynamic geometries are rendered on top,
+    /// so when only the dynamic geometries have changed, the static geometries are not rendered again.
+    ///
+    /// Changes are detected by comparing the bounding box of each geometry, the light direction, the texture size and the [ShadowBias] with the last update.
+    /// Changes which do not affect any bounding box, for example a rotation of a geometry around its center or a change of its shape, are not detected,
+    /// so call [DirectionalLight::mark_static_shadow_casters_changed] or [DirectionalLight::mark_dynamic_shadow_casters_changed] before updating
+    /// to force the changed geometries to be rendered again.
+    /// Note that the area covered by the shadow map is fitted to both the static and dynamic geometries,
+    /// so the static geometries are also rendered again when the dynamic geometries move outside the bounds of the static geometries.
+    ///
+    pub fn update_shadow_map(
+        &mut self,
+        texture_size: u32,
+        static_geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+        dynamic_geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        self.update_shadow_map_internal(
+            texture_size,
+            static_geometries,
+            dynamic_geometries,
+            geometry_depth_material,
+            geometry_depth_material,
+        );
+    }
+
+    ///
+    /// Updates the shadow map in the same way as [DirectionalLight::update_shadow_map], except that each object is rendered using its [depth material](Object::depth_material),
+    /// see [DirectionalLight::generate_shadow_map_with_objects].
+    ///
+    pub fn update_shadow_map_with_objects(
+        &mut self,
+        texture_size: u32,
+        static_objects: impl IntoIterator<Item = impl Object> + Clone,
+        dynamic_objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        self.update_shadow_map_internal(
+            texture_size,
+            static_objects,
+            dynamic_objects,
+            object_depth_material,
+            object_depth_material,
+        );
+    }
+
+    ///
+    /// Marks the static shadow casters as changed, so that both the static and the dynamic shadow casters are rendered again at the next [DirectionalLight::update_shadow_map].
+    /// Use this when a static shadow caster has changed without changing its bounding box, for example when it is rotated around its center or deformed.
+    ///
+    pub fn mark_static_shadow_casters_changed(&mut self) {
+        self.shadow_cache.mark_static_changed();
+    }
+
+    ///
+    /// Marks the dynamic shadow casters as changed, so that they are rendered again on top of the cached static shadow casters at the next [DirectionalLight::update_shadow_map].
+    /// Use this when a dynamic shadow caster has changed without changing its bounding box, for example when it is rotated around its center or deformed.
+    ///
+    pub fn mark_dynamic_shadow_casters_changed(&mut self) {
+        self.shadow_cache.mark_dynamic_changed();
+    }
+
+    fn update_shadow_map_internal<S: Geometry, D: Geometry>(
+        &mut self,
+        texture_size: u32,
+        static_geometries: impl IntoIterator<Item = S> + Clone,
+        dynamic_geometries: impl IntoIterator<Item = D> + Clone,
+        static_depth_material: impl Fn(&S) -> Option<DepthMaterial>,
+        dynamic_depth_material: impl Fn(&D) -> Option<DepthMaterial>,
+    ) {
+        let mut aabb = AxisAlignedBoundingBox::EMPTY;
+        for geometry in static_geometries.clone() {
+            aabb.expand_with_aabb(geometry.aabb());
+        }
+        for geometry in dynamic_geometries.clone() {
+            aabb.expand_with_aabb(geometry.aabb());
+        }
+        if let Some(shadow_camera) = self.shadow_camera(texture_size, aabb) {
+            let mut shadow_texture =
+                reuse_shadow_texture(&self.context, self.shadow_texture.take(), texture_size);
+            self.shadow_cache.update(
+                &self.context,
+                &shadow_camera,
+                &mut shadow_texture,
+                static_geometries,
+                dynamic_geometries,
+                static_depth_material,
+                dynamic_depth_material,
+                &self.shadow_bias,
+            );
+            self.cascaded_shadow_texture = None;
+            self.transmittance_map = None;
+            self.shadow_texture = Some(shadow_texture);
+            self.shadow_matrix = shadow_matrix(&shadow_camera);
+        }
+    }
+
+    ///
+    /// Returns the camera used to render a shadow map which covers the given bounding box or `None` if the bounding box is empty.
+    ///
+    fn shadow_camera(&self, texture_size: u32, aabb: AxisAlignedBoundingBox) -> Option<Camera> {
         if aabb.is_empty() {
-            return;
+            return None;
         }
+        let up = compute_up_direction(self.direction);
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
         let position = aabb.center();
         let target = position + self.direction.normalize();
         let z_far = aabb.distance_max(position);
         let z_near = -z_far;
         let frustum_height = aabb.max().distance(aabb.min()); // TODO: more tight fit
-        let shadow_camera = Camera::new_orthographic(
+        Some(Camera::new_orthographic(
             viewport,
             position,
             target,
@@ -79,43 +295,177 @@ impl DirectionalLight {
             frustum_height,
             z_near,
             z_far,
-        );
-        let mut shadow_texture = DepthTexture2D::new::<f32>(
-            &self.context,
+        ))
+    }
+
+    ///
+    /// Generate a cascaded shadow map which is used to simulate shadows from the directional light onto the geometries given as input.
+    /// The part of the view frustum of the given viewer that contains geometries is split into `cascade_count` slices (at most [MAX_SHADOW_CASCADES]),
+    /// each of which is rendered into a layer of a [DepthTexture2DArray] with the given texture size.
+    /// The slices close to the viewer are smaller than the slices far away, which means that the shadows close to the viewer have a higher resolution.
+    /// The shader selects the cascade based on the view depth so the shadow map needs to be regenerated whenever the viewer changes.
+    /// It is recomended that the texture size is power of 2.
+    /// The shadow texture is reused if it already exists and has the given size and number of cascades.
+    ///
+    pub fn generate_cascaded_shadow_map(
+        &mut self,
+        texture_size: u32,
+        cascade_count: u32,
+        viewer: impl Viewer,
+        geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        self.generate_cascaded_shadow_map_internal(
             texture_size,
+            cascade_count,
+            viewer,
+            geometries,
+            geometry_depth_material,
+        );
+    }
+
+    ///
+    /// Generate a cascaded shadow map in the same way as [DirectionalLight::generate_cascaded_shadow_map], except that each object is rendered using its [depth material](Object::depth_material),
+    /// see [DirectionalLight::generate_shadow_map_with_objects].
+    ///
+    pub fn generate_cascaded_shadow_map_with_objects(
+        &mut self,
+        texture_size: u32,
+        cascade_count: u32,
+        viewer: impl Viewer,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        self.generate_cascaded_shadow_map_internal(
             texture_size,
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
+            cascade_count,
+            viewer,
+            objects,
+            object_depth_material,
         );
-        let depth_material = DepthMaterial {
-            render_states: RenderStates {
-                write_mask: WriteMask::DEPTH,
-                ..Default::default()
-            },
-            ..Default::default()
+    }
+
+    fn generate_cascaded_shadow_map_internal<G: Geometry>(
+        &mut self,
+        texture_size: u32,
+        cascade_count: u32,
+        viewer: impl Viewer,
+        geometries: impl IntoIterator<Item = G> + Clone,
+        depth_material: impl Fn(&G) -> Option<DepthMaterial>,
+    ) {
+        let cascade_count = cascade_count.clamp(1, MAX_SHADOW_CASCADES);
+        let mut aabb = AxisAlignedBoundingBox::EMPTY;
+        for geometry in geometries.clone() {
+            aabb.expand_with_aabb(geometry.aabb());
+        }
+        if aabb.is_empty() {
+            return;
+        }
+
+        // Only split the part of the view frustum which contains geometries
+        let view_aabb = aabb.transformed(viewer.view());
+        let z_near = viewer.z_near().max(-view_aabb.max().z).max(0.01);
+        let z_far = viewer.z_far().min(-view_aabb.min().z);
+        if z_far <= z_near {
+            return;
+        }
+        let splits = compute_cascade_splits(z_near, z_far, cascade_count);
+
+        // The corners of the view frustum at the near and far plane of the viewer
+        let inverse_view_projection = (viewer.projection() * viewer.view()).invert().unwrap();
+        let frustum_corner = |x: f32, y: f32, z: f32| {
+            let p = inverse_view_projection * vec4(x, y, z, 1.0);
+            p.truncate() / p.w
         };
-        let frustum = shadow_camera.frustum();
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write::<RendererError>(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| frustum.contains(g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
-                }
-                Ok(())
-            })
-            .unwrap();
-        self.shadow_texture = Some(shadow_texture);
-        self.shadow_matrix = shadow_matrix(&shadow_camera);
+        let frustum_edges = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+            .map(|(x, y)| (frustum_corner(x, y, -1.0), frustum_corner(x, y, 1.0)));
+        let corner_at_depth = |edge: (Vec3, Vec3), depth: f32| {
+            let t = (depth - viewer.z_near()) / (viewer.z_far() - viewer.z_near());
+            edge.0 + (edge.1 - edge.0) * t
+        };
+
+        let direction = self.direction.normalize();
+        let up = compute_up_direction(direction);
+        let right = direction.cross(up).normalize();
+        let up = right.cross(direction);
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+        let mut shadow_texture = match self.cascaded_shadow_texture.take() {
+            Some(texture)
+                if texture.width() == texture_size
+                    && texture.height() == texture_size
+                    && texture.depth() == cascade_count =>
+            {
+                texture
+            }
+            _ => DepthTexture2DArray::new::<f32>(
+                &self.context,
+                texture_size,
+                texture_size,
+                cascade_count,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            ),
+        };
+        let mut cascade_matrices = [Mat4::identity(); MAX_SHADOW_CASCADES as usize];
+        let mut cascade_splits = [z_far; MAX_SHADOW_CASCADES as usize];
+        for cascade in 0..cascade_count as usize {
+            let corners = frustum_edges
+                .iter()
+                .flat_map(|edge| {
+                    [
+                        corner_at_depth(*edge, splits[cascade]),
+                        corner_at_depth(*edge, splits[cascade + 1]),
+                    ]
+                })
+                .collect::<Vec<_>>();
+
+            // Use a bounding sphere of the slice, so the size of the shadow camera is stable when the viewer rotates
+            let mut center = corners.iter().sum::<Vec3>() / corners.len() as f32;
+            let radius = corners
+                .iter()
+                .map(|c| c.distance(center))
+                .fold(0.0f32, f32::max)
+                .max(0.001);
+
+            // Snap the center to the texels of the shadow map to avoid shimmering edges when the viewer moves
+            let texel_size = 2.0 * radius / texture_size as f32;
+            let x = center.dot(right);
+            let y = center.dot(up);
+            center += right * ((x / texel_size).floor() * texel_size - x)
+                + up * ((y / texel_size).floor() * texel_size - y);
+
+            let shadow_camera = Camera::new_orthographic(
+                viewport,
+                center,
+                center + direction,
+                up,
+                2.0 * radius,
+                -aabb.distance_max(center).max(radius),
+                radius,
+            );
+            render_shadow_map(
+                &self.context,
+                &shadow_camera,
+                shadow_texture.as_depth_target(cascade as u32),
+                geometries.clone(),
+                &depth_material,
+                &self.shadow_bias,
+                None,
+            );
+            cascade_matrices[cascade] = shadow_matrix(&shadow_camera);
+            cascade_splits[cascade] = splits[cascade + 1];
+        }
+        self.clear_shadow_map();
+        self.cascaded_shadow_texture = Some(shadow_texture);
+        self.cascade_matrices = cascade_matrices;
+        self.cascade_splits = cascade_splits.into();
+        self.cascade_view = viewer.view();
+    }
+
+    ///
+    /// Returns a reference to the cascaded shadow map if it has been generated.
+    /// Each layer of the texture contains the shadow map for one cascade, starting with the cascade closest to the viewer.
+    ///
+    pub fn cascaded_shadow_map(&self) -> Option<&DepthTexture2DArray> {
+        self.cascaded_shadow_texture.as_ref()
     }
 
     ///
@@ -128,22 +478,56 @@ impl DirectionalLight {
 
 impl Light for DirectionalLight {
     fn shader_source(&self, i: u32) -> String {
-        if self.shadow_texture.is_some() {
+        if self.cascaded_shadow_texture.is_some() {
+            format!(
+                "
+                    uniform sampler2DArray shadowMap{};
+                    uniform mat4 shadowMVP{}[{}];
+                    uniform vec4 cascadeSplits{};
+                    uniform mat4 cascadeView{};
+                    uniform vec4 shadowFilter{};
+                    uniform vec4 shadowBias{};
+
+                    uniform vec3 color{};
+                    uniform vec3 direction{};
+
+                    vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                    {{
+                        float visibility = 1.0;
+                        int cascade = shadow_cascade(cascadeView{}, cascadeSplits{}, position);
+                        if (cascade < {}) {{
+                            vec3 coord = shadow_coord(shadowMVP{}[cascade], position, normal, -direction{}, shadowBias{});
+                            visibility = {};
+                        }}
+                        return calculate_light(color{}, -direction{}, surface_color, view_direction, normal, metallic, roughness) * visibility;
+                    }}
+
+                ", i, i, MAX_SHADOW_CASCADES, i, i, i, i, i, i, i, i, i, MAX_SHADOW_CASCADES, i, i, i,
+                self.shadow_filter.shader_call(&format!("shadowMap{}", i), "float(cascade)", "coord", &format!("shadowFilter{}", i)),
+                i, i)
+        } else if self.shadow_texture.is_some() {
+            let (transmittance_uniforms, transmittance) =
+                TransmittanceMap::shader_source(self.transmittance_map.as_ref(), i);
             format!(
                 "
+                    {transmittance_uniforms}
                     uniform sampler2D shadowMap{};
                     uniform mat4 shadowMVP{};
+                    uniform vec4 shadowFilter{};
+                    uniform vec4 shadowBias{};
 
                     uniform vec3 color{};
                     uniform vec3 direction{};
 
                     vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
+                        vec3 coord = shadow_coord(shadowMVP{}, position, normal, -direction{}, shadowBias{});
                         return calculate_light(color{}, -direction{}, surface_color, view_direction, normal, metallic, roughness)
-                            * calculate_shadow(-direction{}, normal, shadowMap{}, shadowMVP{}, position);
+                            * {}{transmittance};
                     }}
 
-                ", i, i, i, i, i, i, i, i, i, i)
+                ", i, i, i, i, i, i, i, i, i, i, i, i,
+                self.shadow_filter.shader_call(&format!("shadowMap{}", i), "0.0", "coord", &format!("shadowFilter{}", i)))
         } else {
             format!(
                 "
@@ -163,6 +547,20 @@ impl Light for DirectionalLight {
             program.use_depth_texture(&format!("shadowMap{}", i), tex);
             program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
         }
+        if let Some(ref transmittance_map) = self.transmittance_map {
+            transmittance_map.use_uniforms(program, i);
+        }
+        if let Some(ref tex) = self.cascaded_shadow_texture {
+            program.use_depth_texture_array(&format!("shadowMap{}", i), tex);
+            program.use_uniform_array(&format!("shadowMVP{}", i), &self.cascade_matrices);
+            program.use_uniform(&format!("cascadeSplits{}", i), self.cascade_splits);
+            program.use_uniform(&format!("cascadeView{}", i), self.cascade_view);
+        }
+        program.use_uniform_if_required(
+            &format!("shadowFilter{}", i),
+            self.shadow_filter.parameters(),
+        );
+        program.use_uniform_if_required(&format!("shadowBias{}", i), self.shadow_bias.parameters());
         program.use_uniform(
             &format!("color{}", i),
             self.color.to_linear_srgb().truncate() * self.intensity,
@@ -171,6 +569,31 @@ impl Light for DirectionalLight {
     }
 
     fn id(&self) -> LightId {
-        LightId::DirectionalLight(self.shadow_texture.is_some())
+        let has_shadow = self.shadow_texture.is_some() || self.cascaded_shadow_texture.is_some();
+        LightId::DirectionalLight(
+            self.shadow_texture.is_some(),
+            self.cascaded_shadow_texture.is_some(),
+            self.transmittance_map.is_some(),
+            if has_shadow {
+                self.shadow_filter
+            } else {
+                ShadowFilter::None
+            },
+        )
     }
 }
+
+///
+/// Returns the view depths that splits the range between `z_near` and `z_far` into the given number of cascades.
+/// Uses the practical split scheme which is an even blend between a logarithmic and a uniform distribution.
+///
+fn compute_cascade_splits(z_near: f32, z_far: f32, cascade_count: u32) -> Vec<f32> {
+    (0..=cascade_count)
+        .map(|i| {
+            let p = i as f32 / cascade_count as f32;
+            let logarithmic = z_near * (z_far / z_near).powf(p);
+            let uniform = z_near + (z_far - z_near) * p;
+            0.5 * logarithmic + 0.5 * uniform
+        })
+        .collect()
+}
@@ -0,0 +1,94 @@
+use crate::core::*;
+use crate::renderer::light::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// A light which shines from a disk, for example a round ceiling lamp.
+/// The lighting is calculated using linearly transformed cosines fitted to the GGX distribution, where the disk is approximated by a regular polygon with 16 sides,
+/// so the light has soft specular highlights with the shape of the disk and the lighting model of the material is ignored.
+/// The disk only emits light from the side facing the direction of the light and it does not cast shadows.
+///
+pub struct DiskLight {
+    ltc: Arc<LtcTables>,
+    /// The intensity of the light, ie. the radiance emitted from each point on the disk.
+    pub intensity: f32,
+    /// The base color of the light.
+    pub color: Srgba,
+    /// The center of the disk.
+    pub position: Vec3,
+    /// The direction the light shines, ie. the normal of the disk.
+    pub direction: Vec3,
+    /// The radius of the disk.
+    pub radius: f32,
+}
+
+impl DiskLight {
+    /// Constructs a new disk light.
+    pub fn new(
+        context: &Context,
+        intensity: f32,
+        color: Srgba,
+        position: Vec3,
+        direction: Vec3,
+        radius: f32,
+    ) -> DiskLight {
+        DiskLight {
+            ltc: LtcTables::shared(context),
+            intensity,
+            color,
+            position,
+            direction,
+            radius,
+        }
+    }
+}
+
+impl Light for DiskLight {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+        "
+            uniform sampler2D ltcMatrix{};
+            uniform sampler2D ltcAmplitude{};
+
+            uniform vec3 color{};
+            uniform vec3 position{};
+            uniform vec3 direction{};
+            uniform vec3 radiusX{};
+            uniform vec3 radiusY{};
+
+            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+            {{
+                if (dot(direction{}, position - position{}) <= 0.0) {{
+                    return vec3(0.0);
+                }}
+                vec2 uv = ltc_uv(normal, view_direction, roughness);
+                mat3 specular_transform = ltc_transform(texture(ltcMatrix{}, uv), normal, view_direction);
+                mat3 diffuse_transform = ltc_transform(vec4(1.0, 0.0, 0.0, 1.0), normal, view_direction);
+                return ltc_light(color{}, surface_color, metallic, texture(ltcAmplitude{}, uv).xy,
+                    ltc_evaluate_disk(diffuse_transform, position, position{}, radiusX{}, radiusY{}),
+                    ltc_evaluate_disk(specular_transform, position, position{}, radiusX{}, radiusY{}));
+            }}
+
+        ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        let direction = self.direction.normalize();
+        let up = compute_up_direction(direction);
+        let right = direction.cross(up);
+        self.ltc.use_uniforms(program, i);
+        program.use_uniform(
+            &format!("color{}", i),
+            self.color.to_linear_srgb().truncate() * self.intensity,
+        );
+        program.use_uniform(&format!("position{}", i), self.position);
+        program.use_uniform(&format!("direction{}", i), direction);
+        program.use_uniform(&format!("radiusX{}", i), right * self.radius);
+        program.use_uniform(&format!("radiusY{}", i), up * self.radius);
+    }
+
+    fn id(&self) -> LightId {
+        LightId::DiskLight
+    }
+}
@@ -7,6 +7,7 @@ use crate::renderer::*;
 ///
 pub struct Environment {
     /// A cube map used to calculate the diffuse contribution from the environment.
+    /// This is an empty placeholder if the environment is constructed using [Environment::new_without_irradiance_map].
     pub irradiance_map: TextureCubeMap,
     /// A cube map used to calculate the specular contribution from the environment.
     /// Each mip-map level contain the prefiltered color for a certain surface roughness.
@@ -38,9 +39,36 @@ impl Environment {
         context: &Context,
         environment_map: &TextureCubeMap,
         lighting_model: LightingModel,
+    ) -> Self {
+        Self::new_internal(context, environment_map, lighting_model, true)
+    }
+
+    ///
+    /// Computes the maps needed for the specular contribution from the environment in the same way as [Environment::new],
+    /// but skips the expensive convolution of the environment map into the irradiance map.
+    /// Use this when the diffuse contribution is calculated using [SphericalHarmonics] instead,
+    /// see [AmbientLight::new_with_environment_and_spherical_harmonics].
+    ///
+    pub fn new_without_irradiance_map(context: &Context, environment_map: &TextureCubeMap) -> Self {
+        Self::new_internal(
+            context,
+            environment_map,
+            LightingModel::Cook(
+                NormalDistributionFunction::TrowbridgeReitzGGX,
+                GeometryFunction::SmithSchlickGGX,
+            ),
+            false,
+        )
+    }
+
+    fn new_internal(
+        context: &Context,
+        environment_map: &TextureCubeMap,
+        lighting_model: LightingModel,
+        irradiance: bool,
     ) -> Self {
         // Diffuse
-        let irradiance_size = 32;
+        let irradiance_size = if irradiance { 32 } else { 1 };
         let mut irradiance_map = TextureCubeMap::new_empty::<[f16; 4]>(
             context,
             irradiance_size,
@@ -52,7 +80,7 @@ impl Environment {
             Wrapping::ClampToEdge,
             Wrapping::ClampToEdge,
         );
-        {
+        if irradiance {
             let viewport = Viewport::new_at_origo(irradiance_size, irradiance_size);
             for side in CubeMapSide::iter() {
                 irradiance_map
@@ -0,0 +1,91 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A light which shines on all surfaces from a sky above and a ground below, for example an overcast sky over a lawn.
+/// The light received by a surface is blended between the sky color and the ground color depending on how much the surface faces the up direction.
+/// This is a cheap alternative to an [AmbientLight] calculated from an [Environment], which does not require an environment map,
+/// while still giving more shape to the objects than a uniform [AmbientLight].
+///
+pub struct HemisphereLight {
+    /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
+    pub intensity: f32,
+    /// The color of the light shining from the sky, ie. on surfaces facing the up direction.
+    pub sky_color: Srgba,
+    /// The color of the light shining from the ground, ie. on surfaces facing away from the up direction.
+    pub ground_color: Srgba,
+    /// The direction pointing towards the sky.
+    pub up: Vec3,
+}
+
+impl HemisphereLight {
+    /// Constructs a new hemisphere light.
+    pub fn new(
+        _context: &Context,
+        intensity: f32,
+        sky_color: Srgba,
+        ground_color: Srgba,
+        up: Vec3,
+    ) -> Self {
+        Self {
+            intensity,
+            sky_color,
+            ground_color,
+            up,
+        }
+    }
+}
+
+impl Light for HemisphereLight {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+        "
+            uniform vec3 skyColor{};
+            uniform vec3 groundColor{};
+            uniform vec3 up{};
+
+            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+            {{
+                float NdV = max(0.001, dot(normal, view_direction));
+                vec3 specular_fresnel = surface_fresnel(surface_color, metallic, NdV, roughness);
+                vec3 diffuse_fresnel = 1.0 - specular_fresnel;
+
+                // Diffuse
+                vec3 irradiance = mix(groundColor{}, skyColor{}, 0.5 + 0.5 * dot(normal, up{}));
+                vec3 diffuse = diffuse_fresnel * surface_diffuse(surface_color, metallic) * irradiance;
+
+                // The reflected light is blurred towards the light shining onto the surface as the roughness increases
+                vec3 reflection = normalize(mix(reflect(-view_direction, normal), normal, roughness * roughness));
+                vec3 specular = specular_fresnel * mix(groundColor{}, skyColor{}, 0.5 + 0.5 * dot(reflection, up{}));
+                vec3 sheen = sheen_reflection(irradiance);
+
+                vec3 color = diffuse + specular + sheen;
+                if (material_clearcoat > 0.0) {{
+                    vec3 clearcoat_normal = material_clearcoat_normal;
+                    vec3 clearcoat_reflection = normalize(mix(reflect(-view_direction, clearcoat_normal), clearcoat_normal, material_clearcoat_roughness * material_clearcoat_roughness));
+                    vec3 clearcoat_color = mix(groundColor{}, skyColor{}, 0.5 + 0.5 * dot(clearcoat_reflection, up{})) * clearcoat_fresnel(max(0.001, dot(clearcoat_normal, view_direction)));
+                    color = add_clearcoat(color, clearcoat_color, view_direction);
+                }}
+
+                return color * occlusion * material_ambient_factor;
+            }}
+
+        ", i, i, i, i, i, i, i, i, i, i, i, i, i)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        program.use_uniform(
+            &format!("skyColor{}", i),
+            self.sky_color.to_linear_srgb().truncate() * self.intensity,
+        );
+        program.use_uniform(
+            &format!("groundColor{}", i),
+            self.ground_color.to_linear_srgb().truncate() * self.intensity,
+        );
+        program.use_uniform(&format!("up{}", i), self.up.normalize());
+    }
+
+    fn id(&self) -> LightId {
+        LightId::HemisphereLight
+    }
+}
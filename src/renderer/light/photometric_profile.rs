@@ -0,0 +1,224 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// A photometric profile parsed from an IES file (IESNA LM-63), which describes the luminous intensity of a light fixture in all directions.
+/// Only type C photometry, which is used by almost all architectural light fixtures, is supported.
+///
+/// The angles are in degrees. The vertical angles are measured from the direction the fixture is pointing (0° is straight ahead)
+/// and the horizontal angles are measured counterclockwise around the pointing direction when looking in the pointing direction.
+///
+#[derive(Clone, Debug)]
+pub struct IesProfile {
+    /// The vertical angles in increasing order.
+    pub vertical_angles: Vec<f32>,
+    /// The horizontal angles in increasing order.
+    /// The last horizontal angle determines the symmetry of the profile: 0° means rotationally symmetric,
+    /// 90° means symmetric in each quadrant, 180° means symmetric around the 0°-180° plane and 360° means no symmetry.
+    pub horizontal_angles: Vec<f32>,
+    /// The luminous intensity in candela for each horizontal angle (outer) and vertical angle (inner).
+    pub candela: Vec<Vec<f32>>,
+}
+
+impl IesProfile {
+    /// The width of the texture returned by [IesProfile::to_cpu_texture], ie. the resolution of the vertical angles.
+    pub const TEXTURE_WIDTH: u32 = 256;
+    /// The height of the texture returned by [IesProfile::to_cpu_texture], ie. the resolution of the horizontal angles.
+    pub const TEXTURE_HEIGHT: u32 = 64;
+
+    ///
+    /// Parses the content of an IES file.
+    ///
+    pub fn parse(text: &str) -> Result<Self, RendererError> {
+        let error = |message: &str| RendererError::InvalidIesProfile(message.to_string());
+        let mut lines = text.lines();
+        let tilt = lines
+            .by_ref()
+            .map(|line| line.trim())
+            .find(|line| line.starts_with("TILT="))
+            .ok_or_else(|| error("missing TILT line"))?;
+        let mut values = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| error(&format!("invalid number {}", value)))
+            });
+        let mut next = || {
+            values
+                .next()
+                .unwrap_or_else(|| Err(error("unexpected end of file")))
+        };
+
+        if tilt == "TILT=INCLUDE" {
+            // Lamp to luminaire geometry followed by the tilt angles and multiplying factors
+            next()?;
+            let count = next()? as usize;
+            for _ in 0..2 * count {
+                next()?;
+            }
+        }
+        let _lamp_count = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()?;
+        if photometric_type != 1.0 {
+            return Err(error("only type C photometry is supported"));
+        }
+        // Units, width, length, height, ballast factor, future use and input watts
+        for _ in 0..7 {
+            next()?;
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(error("the profile contains no angles"));
+        }
+        let vertical_angles = (0..vertical_count)
+            .map(|_| next())
+            .collect::<Result<Vec<_>, _>>()?;
+        let horizontal_angles = (0..horizontal_count)
+            .map(|_| next())
+            .collect::<Result<Vec<_>, _>>()?;
+        let candela = (0..horizontal_count)
+            .map(|_| {
+                (0..vertical_count)
+                    .map(|_| next().map(|value| value * multiplier))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+        })
+    }
+
+    ///
+    /// Returns the maximum luminous intensity in candela.
+    ///
+    pub fn max_candela(&self) -> f32 {
+        self.candela.iter().flatten().fold(0.0, |a, b| a.max(*b))
+    }
+
+    ///
+    /// Returns the largest vertical angle with a non-zero intensity,
+    /// which can be used as the [cutoff](SpotLight::cutoff) of a [SpotLight] using this profile.
+    ///
+    pub fn cutoff(&self) -> Degrees {
+        let angle = self
+            .vertical_angles
+            .iter()
+            .enumerate()
+            .filter(|(v, _)| self.candela.iter().any(|values| values[*v] > 0.0))
+            .map(|(_, angle)| *angle)
+            .fold(0.0, f32::max);
+        degrees(angle)
+    }
+
+    ///
+    /// Returns the luminous intensity in candela at the given vertical and horizontal angle in degrees.
+    /// The intensity is interpolated linearly between the angles in the profile and is zero outside the vertical angles.
+    ///
+    pub fn candela(&self, vertical_angle: f32, horizontal_angle: f32) -> f32 {
+        let first = self.horizontal_angles[0];
+        let last = *self.horizontal_angles.last().unwrap();
+        let mut horizontal = horizontal_angle.rem_euclid(360.0);
+        if last <= 0.0 {
+            horizontal = 0.0;
+        } else if last <= 90.0 {
+            horizontal %= 180.0;
+            if horizontal > 90.0 {
+                horizontal = 180.0 - horizontal;
+            }
+        } else if first >= 90.0 && last <= 270.0 {
+            // Symmetric around the 90°-270° plane
+            if horizontal < 90.0 {
+                horizontal = 180.0 - horizontal;
+            } else if horizontal > 270.0 {
+                horizontal = 540.0 - horizontal;
+            }
+        } else if last <= 180.0 && horizontal > 180.0 {
+            horizontal = 360.0 - horizontal;
+        }
+        let (h0, h1, ht) = interpolation(&self.horizontal_angles, horizontal);
+        if vertical_angle < self.vertical_angles[0]
+            || vertical_angle > *self.vertical_angles.last().unwrap()
+        {
+            return 0.0;
+        }
+        let (v0, v1, vt) = interpolation(&self.vertical_angles, vertical_angle);
+        let at = |h: usize| self.candela[h][v0] + (self.candela[h][v1] - self.candela[h][v0]) * vt;
+        at(h0) + (at(h1) - at(h0)) * ht
+    }
+
+    ///
+    /// Returns a texture containing the luminous intensity relative to the [maximum intensity](IesProfile::max_candela),
+    /// where the u coordinate maps to the vertical angles from 0° to 180° and the v coordinate maps to the horizontal angles from 0° to 360°.
+    ///
+    pub fn to_cpu_texture(&self) -> CpuTexture {
+        let max = self.max_candela().max(f32::EPSILON);
+        let mut data = Vec::with_capacity((Self::TEXTURE_WIDTH * Self::TEXTURE_HEIGHT) as usize);
+        for y in 0..Self::TEXTURE_HEIGHT {
+            // The first row is the top of the texture, ie. the largest v coordinate
+            let horizontal = 360.0 * (1.0 - (y as f32 + 0.5) / Self::TEXTURE_HEIGHT as f32);
+            for x in 0..Self::TEXTURE_WIDTH {
+                let vertical = 180.0 * (x as f32 + 0.5) / Self::TEXTURE_WIDTH as f32;
+                data.push(f16::from_f32(self.candela(vertical, horizontal) / max));
+            }
+        }
+        CpuTexture {
+            name: "IES profile".to_string(),
+            data: TextureData::RF16(data),
+            width: Self::TEXTURE_WIDTH,
+            height: Self::TEXTURE_HEIGHT,
+            min_filter: Interpolation::Linear,
+            mag_filter: Interpolation::Linear,
+            mipmap: None,
+            wrap_s: Wrapping::ClampToEdge,
+            wrap_t: Wrapping::Repeat,
+        }
+    }
+}
+
+///
+/// Returns the indices of the two angles surrounding the given angle and the interpolation factor between them.
+///
+fn interpolation(angles: &[f32], angle: f32) -> (usize, usize, f32) {
+    let i1 = angles
+        .iter()
+        .position(|a| *a >= angle)
+        .unwrap_or(angles.len() - 1);
+    let i0 = i1.saturating_sub(1);
+    if i0 == i1 || angles[i1] <= angles[i0] {
+        (i1, i1, 0.0)
+    } else {
+        (i0, i1, (angle - angles[i0]) / (angles[i1] - angles[i0]))
+    }
+}
+
+///
+/// A photometric profile on the GPU which is used to give a [SpotLight] the light distribution of a real light fixture, see [SpotLight::photometric_profile].
+///
+#[derive(Clone)]
+pub struct PhotometricProfile {
+    /// The texture containing the relative luminous intensity, see [IesProfile::to_cpu_texture].
+    pub texture: Arc<Texture2D>,
+    /// The direction of the horizontal angle 0°.
+    /// It is projected onto the plane perpendicular to the direction of the light, so it only needs to be roughly perpendicular to the light direction.
+    pub up: Vec3,
+}
+
+impl PhotometricProfile {
+    ///
+    /// Constructs a new photometric profile from the given IES profile.
+    ///
+    pub fn new(context: &Context, profile: &IesProfile, up: Vec3) -> Self {
+        Self {
+            texture: Arc::new(Texture2D::new(context, &profile.to_cpu_texture())),
+            up,
+        }
+    }
+}
@@ -1,10 +1,15 @@
 use crate::core::*;
+use crate::renderer::light::*;
 use crate::renderer::*;
 
 ///
 /// A light which shines from the given position in all directions.
+/// The light will cast shadows if you [generate a shadow map](PointLight::generate_shadow_map).
 ///
 pub struct PointLight {
+    context: Context,
+    shadow_texture: Option<DepthTextureCubeMap>,
+    shadow_range: Vec2,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
@@ -18,42 +23,186 @@ pub struct PointLight {
 impl PointLight {
     /// Constructs a new point light.
     pub fn new(
-        _context: &Context,
+        context: &Context,
         intensity: f32,
         color: Srgba,
         position: Vec3,
         attenuation: Attenuation,
     ) -> PointLight {
         PointLight {
+            context: context.clone(),
+            shadow_texture: None,
+            shadow_range: Vec2::zero(),
             intensity,
             color,
             position,
             attenuation,
         }
     }
+
+    ///
+    /// Clear the shadow map, effectively disable the shadow.
+    /// Only necessary if you want to disable the shadow, if you want to update the shadow, just use [PointLight::generate_shadow_map].
+    ///
+    pub fn clear_shadow_map(&mut self) {
+        self.shadow_texture = None;
+        self.shadow_range = Vec2::zero();
+    }
+
+    ///
+    /// Generate a shadow map which is used to simulate shadows from the point light onto the geometries given as input.
+    /// The shadow map is a cube map where each side is rendered from the position of the light, so the geometries are rendered six times.
+    /// It is recomended that the texture size is power of 2.
+    /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size.
+    /// The shadow texture is reused if it already exists and has the given size.
+    ///
+    pub fn generate_shadow_map(
+        &mut self,
+        texture_size: u32,
+        geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        self.generate_shadow_map_internal(texture_size, geometries, geometry_depth_material);
+    }
+
+    ///
+    /// Generate a shadow map in the same way as [PointLight::generate_shadow_map], except that each object is rendered using its [depth material](Object::depth_material),
+    /// see [DirectionalLight::generate_shadow_map_with_objects].
+    ///
+    pub fn generate_shadow_map_with_objects(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        self.generate_shadow_map_internal(texture_size, objects, object_depth_material);
+    }
+
+    fn generate_shadow_map_internal<G: Geometry>(
+        &mut self,
+        texture_size: u32,
+        geometries: impl IntoIterator<Item = G> + Clone,
+        depth_material: impl Fn(&G) -> Option<DepthMaterial>,
+    ) {
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+
+        let mut z_far = 0.0f32;
+        let mut z_near = f32::MAX;
+        for geometry in geometries.clone() {
+            let aabb = geometry.aabb();
+            if !aabb.is_empty() {
+                z_far = z_far.max(aabb.distance_max(self.position));
+                z_near = z_near.min(aabb.distance(self.position));
+            }
+        }
+        if z_far <= 0.0 {
+            return;
+        }
+        let z_near = z_near.clamp(0.01, z_far * 0.5);
+
+        let mut shadow_texture = match self.shadow_texture.take() {
+            Some(texture)
+                if texture.width() == texture_size && texture.height() == texture_size =>
+            {
+                texture
+            }
+            _ => DepthTextureCubeMap::new::<f32>(
+                &self.context,
+                texture_size,
+                texture_size,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            ),
+        };
+        for side in CubeMapSide::iter() {
+            let shadow_camera = Camera::new_perspective(
+                viewport,
+                self.position,
+                self.position + side.direction(),
+                side.up(),
+                degrees(90.0),
+                z_near,
+                z_far,
+            );
+            render_shadow_map(
+                &self.context,
+                &shadow_camera,
+                shadow_texture.as_depth_target(side),
+                geometries.clone(),
+                &depth_material,
+                &ShadowBias::default(),
+                None,
+            );
+        }
+        self.shadow_texture = Some(shadow_texture);
+        self.shadow_range = vec2(z_near, z_far);
+    }
+
+    ///
+    /// Returns the distance from the light beyond which the light has no visible effect, see [Attenuation::range].
+    ///
+    pub fn range(&self) -> f32 {
+        let color = self.color.to_linear_srgb();
+        self.attenuation
+            .range(self.intensity * color.x.max(color.y).max(color.z))
+    }
+
+    ///
+    /// Returns a reference to the shadow map if it has been generated.
+    ///
+    pub fn shadow_map(&self) -> Option<&DepthTextureCubeMap> {
+        self.shadow_texture.as_ref()
+    }
 }
 
 impl Light for PointLight {
     fn shader_source(&self, i: u32) -> String {
-        format!(
-        "
-            uniform vec3 color{};
-            uniform vec3 attenuation{};
-            uniform vec3 position{};
-
-            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
-            {{
-                vec3 light_direction = position{} - position;
-                float distance = length(light_direction);
-                light_direction = light_direction / distance;
-
-                vec3 light_color = attenuate(color{}, attenuation{}, distance);
-                return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
-            }}
-        
-        ", i, i, i, i, i, i, i)
+        if self.shadow_texture.is_some() {
+            format!(
+            "
+                uniform samplerCube shadowMap{};
+                uniform vec2 shadowRange{};
+
+                uniform vec3 color{};
+                uniform vec3 attenuation{};
+                uniform vec3 position{};
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 light_direction = position{} - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    vec3 light_color = attenuate(color{}, attenuation{}, distance);
+                    return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness)
+                        * calculate_omnidirectional_shadow(light_direction, normal, shadowMap{}, shadowRange{}, position{}, position);
+                }}
+
+            ", i, i, i, i, i, i, i, i, i, i, i, i)
+        } else {
+            format!(
+            "
+                uniform vec3 color{};
+                uniform vec3 attenuation{};
+                uniform vec3 position{};
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 light_direction = position{} - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    vec3 light_color = attenuate(color{}, attenuation{}, distance);
+                    return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
+                }}
+
+            ", i, i, i, i, i, i, i)
+        }
     }
     fn use_uniforms(&self, program: &Program, i: u32) {
+        if let Some(ref tex) = self.shadow_texture {
+            program.use_depth_texture_cube(&format!("shadowMap{}", i), tex);
+            program.use_uniform(&format!("shadowRange{}", i), self.shadow_range);
+        }
         program.use_uniform(
             &format!("color{}", i),
             self.color.to_linear_srgb().truncate() * self.intensity,
@@ -70,6 +219,6 @@ impl Light for PointLight {
     }
 
     fn id(&self) -> LightId {
-        LightId::PointLight
+        LightId::PointLight(self.shadow_texture.is_some())
     }
 }
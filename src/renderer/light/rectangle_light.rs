@@ -0,0 +1,120 @@
+use crate::core::*;
+use crate::renderer::light::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// A light which shines from a rectangle, for example a window or a soft box.
+/// The lighting is calculated using linearly transformed cosines fitted to the GGX distribution,
+/// so the light has soft specular highlights with the shape of the rectangle and the lighting model of the material is ignored.
+/// The rectangle only emits light from the side facing the direction of the light and it does not cast shadows.
+///
+pub struct RectangleLight {
+    ltc: Arc<LtcTables>,
+    /// The intensity of the light, ie. the radiance emitted from each point on the rectangle.
+    pub intensity: f32,
+    /// The base color of the light.
+    pub color: Srgba,
+    /// The center of the rectangle.
+    pub position: Vec3,
+    /// The direction the light shines, ie. the normal of the rectangle.
+    pub direction: Vec3,
+    /// The up direction of the rectangle which, together with the direction, determines the rotation of the rectangle.
+    pub up: Vec3,
+    /// The width of the rectangle.
+    pub width: f32,
+    /// The height of the rectangle, ie. the size in the up direction.
+    pub height: f32,
+}
+
+impl RectangleLight {
+    /// Constructs a new rectangle light.
+    pub fn new(
+        context: &Context,
+        intensity: f32,
+        color: Srgba,
+        position: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        width: f32,
+        height: f32,
+    ) -> RectangleLight {
+        RectangleLight {
+            ltc: LtcTables::shared(context),
+            intensity,
+            color,
+            position,
+            direction,
+            up,
+            width,
+            height,
+        }
+    }
+
+    ///
+    /// Returns the vectors from the center to the edge of the rectangle in the width and height directions.
+    ///
+    fn half_extents(&self) -> (Vec3, Vec3) {
+        let direction = self.direction.normalize();
+        let mut up = self.up - direction * self.up.dot(direction);
+        if up.magnitude2() < 0.0001 {
+            up = compute_up_direction(direction);
+        }
+        let up = up.normalize();
+        let right = direction.cross(up);
+        (right * 0.5 * self.width, up * 0.5 * self.height)
+    }
+}
+
+impl Light for RectangleLight {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+        "
+            uniform sampler2D ltcMatrix{};
+            uniform sampler2D ltcAmplitude{};
+
+            uniform vec3 color{};
+            uniform vec3 position{};
+            uniform vec3 direction{};
+            uniform vec3 halfWidth{};
+            uniform vec3 halfHeight{};
+
+            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+            {{
+                if (dot(direction{}, position - position{}) <= 0.0) {{
+                    return vec3(0.0);
+                }}
+                vec3 corners[4] = vec3[](
+                    position{} - halfWidth{} - halfHeight{},
+                    position{} + halfWidth{} - halfHeight{},
+                    position{} + halfWidth{} + halfHeight{},
+                    position{} - halfWidth{} + halfHeight{}
+                );
+                vec2 uv = ltc_uv(normal, view_direction, roughness);
+                mat3 specular_transform = ltc_transform(texture(ltcMatrix{}, uv), normal, view_direction);
+                mat3 diffuse_transform = ltc_transform(vec4(1.0, 0.0, 0.0, 1.0), normal, view_direction);
+                return ltc_light(color{}, surface_color, metallic, texture(ltcAmplitude{}, uv).xy,
+                    ltc_evaluate_rectangle(diffuse_transform, position, corners),
+                    ltc_evaluate_rectangle(specular_transform, position, corners));
+            }}
+
+        ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        let (half_width, half_height) = self.half_extents();
+        self.ltc.use_uniforms(program, i);
+        program.use_uniform(
+            &format!("color{}", i),
+            self.color.to_linear_srgb().truncate() * self.intensity,
+        );
+        program.use_uniform(&format!("position{}", i), self.position);
+        program.use_uniform(&format!("direction{}", i), self.direction.normalize());
+        program.use_uniform(&format!("halfWidth{}", i), half_width);
+        program.use_uniform(&format!("halfHeight{}", i), half_height);
+    }
+
+    fn id(&self) -> LightId {
+        LightId::RectangleLight
+    }
+}
@@ -0,0 +1,267 @@
+use crate::core::*;
+use crate::renderer::*;
+
+/// The maximum number of reflection probes used by [ReflectionProbes].
+pub const MAX_REFLECTION_PROBES: usize = 4;
+
+///
+/// A reflection probe captures the scene as seen from a point into a cube map, which is used for reflections and diffuse lighting
+/// of the surfaces inside the [influence box](ReflectionProbe::influence) of the probe, see [ReflectionProbes].
+/// Unlike an [Environment], which is infinitely far away, the reflections are corrected for the position of the surface (parallax correction)
+/// by assuming that the captured scene lies on the sides of the influence box, which works well for rooms and other box shaped spaces.
+///
+pub struct ReflectionProbe {
+    context: Context,
+    environment: Option<Environment>,
+    /// The position the scene is captured from.
+    pub position: Vec3,
+    /// The box affected by the probe and onto which the captured scene is projected.
+    /// Typically the walls, floor and ceiling of a room.
+    pub influence: AxisAlignedBoundingBox,
+    /// The distance from the sides of the influence box over which the probe fades out,
+    /// which allows for smooth transitions between overlapping probes.
+    pub blend_distance: f32,
+}
+
+impl ReflectionProbe {
+    ///
+    /// Constructs a new reflection probe at the given position which affects the given box.
+    /// The probe does not contribute to the lighting until it has been [baked](ReflectionProbe::bake).
+    ///
+    pub fn new(context: &Context, position: Vec3, influence: AxisAlignedBoundingBox) -> Self {
+        let size = influence.size();
+        Self {
+            context: context.clone(),
+            environment: None,
+            position,
+            influence,
+            blend_distance: 0.1 * size.x.min(size.y).min(size.z),
+        }
+    }
+
+    ///
+    /// Renders the given objects, lit by the given lights, into a cube map with the given texture size as seen from the position of the probe
+    /// and computes the prefiltered maps used for the lighting in the same way as an [Environment].
+    /// Call this again whenever the scene around the probe has changed enough to be visible in the reflections.
+    ///
+    pub fn bake(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+        lights: &[&dyn Light],
+    ) {
+        let mut z_far = 0.0f32;
+        for object in objects.clone() {
+            let aabb = object.aabb();
+            if !aabb.is_empty() {
+                z_far = z_far.max(aabb.distance_max(self.position));
+            }
+        }
+        let z_far = z_far.max(self.influence.distance_max(self.position));
+        let z_near = (0.001 * z_far).max(0.001);
+
+        let mut environment_map = TextureCubeMap::new_empty::<[f16; 4]>(
+            &self.context,
+            texture_size,
+            texture_size,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            Some(Mipmap::default()),
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let mut depth_texture = DepthTexture2D::new::<f32>(
+            &self.context,
+            texture_size,
+            texture_size,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+        for side in CubeMapSide::iter() {
+            let mut camera = Camera::new_perspective(
+                viewport,
+                self.position,
+                self.position + side.direction(),
+                side.up(),
+                degrees(90.0),
+                z_near,
+                z_far,
+            );
+            // The captured scene is lit again when used, so keep it in linear HDR
+            camera.disable_tone_and_color_mapping();
+            let sides = [side];
+            RenderTarget::new(
+                environment_map.as_color_target(&sides, None),
+                depth_texture.as_depth_target(),
+            )
+            .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 1.0, 1.0))
+            .render(&camera, objects.clone(), lights);
+        }
+        self.environment = Some(Environment::new(&self.context, &environment_map));
+    }
+
+    ///
+    /// Returns the prefiltered maps of the captured scene if the probe has been baked.
+    ///
+    pub fn environment(&self) -> Option<&Environment> {
+        self.environment.as_ref()
+    }
+}
+
+///
+/// A light which shines on the surfaces inside the influence boxes of a set of local [ReflectionProbe]s.
+/// Each surface is lit by the probes containing it, blended by the distance to the sides of the influence boxes,
+/// and receives no light from the probes if it is outside all of them.
+/// Only the first [MAX_REFLECTION_PROBES] baked probes are used, so for larger scenes create a [ReflectionProbes] light for each area
+/// and pass the one containing the objects to the render call.
+///
+pub struct ReflectionProbes {
+    /// The intensity of the light from the probes.
+    pub intensity: f32,
+    /// The reflection probes.
+    pub probes: Vec<ReflectionProbe>,
+}
+
+impl ReflectionProbes {
+    ///
+    /// Constructs a new light from the given reflection probes.
+    ///
+    pub fn new(_context: &Context, intensity: f32, probes: Vec<ReflectionProbe>) -> Self {
+        Self { intensity, probes }
+    }
+
+    fn baked_probes(&self) -> impl Iterator<Item = (&ReflectionProbe, &Environment)> {
+        self.probes
+            .iter()
+            .filter_map(|probe| probe.environment().map(|environment| (probe, environment)))
+            .take(MAX_REFLECTION_PROBES)
+    }
+}
+
+impl Light for ReflectionProbes {
+    fn shader_source(&self, i: u32) -> String {
+        let mut uniforms = String::new();
+        let mut probes = String::new();
+        for p in 0..self.baked_probes().count() {
+            uniforms.push_str(&format!(
+                "
+                uniform samplerCube irradianceMap{}_{};
+                uniform samplerCube prefilterMap{}_{};
+                uniform vec3 probePosition{}_{};
+                uniform vec3 probeMin{}_{};
+                uniform vec3 probeMax{}_{};
+                uniform float probeBlendDistance{}_{};
+                ",
+                i, p, i, p, i, p, i, p, i, p, i, p
+            ));
+            probes.push_str(&format!(
+                "
+                    weight = probe_weight(position, probeMin{}_{}, probeMax{}_{}, probeBlendDistance{}_{});
+                    direction = box_projected_direction(R, position, probePosition{}_{}, probeMin{}_{}, probeMax{}_{});
+                    irradiance += weight * texture(irradianceMap{}_{}, N).rgb;
+                    prefilteredColor += weight * textureLod(prefilterMap{}_{}, direction, roughness * MAX_REFLECTION_LOD).rgb;
+                    total_weight += weight;
+                ", i, p, i, p, i, p, i, p, i, p, i, p, i, p, i, p));
+        }
+        if probes.is_empty() {
+            return format!(
+                "
+                    vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                    {{
+                        return vec3(0.0);
+                    }}
+
+                ", i);
+        }
+        format!(
+            "
+                {}
+                {}
+                uniform sampler2D brdfLUT{};
+                uniform float probeIntensity{};
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 N = normal;
+                    vec3 V = view_direction;
+                    vec3 R = reflect(-V, N);
+                    float NdV = max(0.001, dot(N, V));
+
+                    vec3 specular_fresnel = surface_fresnel(surface_color, metallic, NdV, roughness);
+                    vec3 diffuse_fresnel = 1.0 - specular_fresnel;
+
+                    const float MAX_REFLECTION_LOD = 4.0;
+                    vec3 irradiance = vec3(0.0);
+                    vec3 prefilteredColor = vec3(0.0);
+                    float total_weight = 0.0;
+                    float weight;
+                    vec3 direction;
+                    {}
+                    // Normalize where probes overlap, but fade out at the sides of the outermost probes
+                    float normalization = 1.0 / max(total_weight, 1.0);
+
+                    vec3 diffuse = diffuse_fresnel * surface_diffuse(surface_color, metallic) * irradiance * normalization;
+                    vec2 brdf = texture(brdfLUT{}, vec2(NdV, roughness)).rg;
+                    vec3 specular = prefilteredColor * normalization * (specular_fresnel * brdf.x + brdf.y);
+
+                    return (diffuse + specular) * occlusion * probeIntensity{} * material_ambient_factor;
+                }}
+
+            ", REFLECTION_PROBE_SOURCE, uniforms, i, i, i, probes, i, i)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        for (p, (probe, environment)) in self.baked_probes().enumerate() {
+            program.use_texture_cube(
+                &format!("irradianceMap{}_{}", i, p),
+                &environment.irradiance_map,
+            );
+            program.use_texture_cube(
+                &format!("prefilterMap{}_{}", i, p),
+                &environment.prefilter_map,
+            );
+            program.use_uniform(&format!("probePosition{}_{}", i, p), probe.position);
+            program.use_uniform(&format!("probeMin{}_{}", i, p), probe.influence.min());
+            program.use_uniform(&format!("probeMax{}_{}", i, p), probe.influence.max());
+            program.use_uniform(
+                &format!("probeBlendDistance{}_{}", i, p),
+                probe.blend_distance,
+            );
+            if p == 0 {
+                program.use_texture(&format!("brdfLUT{}", i), &environment.brdf_map);
+                program.use_uniform(&format!("probeIntensity{}", i), self.intensity);
+            }
+        }
+    }
+
+    fn id(&self) -> LightId {
+        LightId::ReflectionProbes(self.baked_probes().count() as u8)
+    }
+}
+
+const REFLECTION_PROBE_SOURCE: &str = "
+    #ifndef REFLECTION_PROBE_FUNCTIONS
+    #define REFLECTION_PROBE_FUNCTIONS
+
+    // Returns the weight of a probe at the given position, which fades from one at the blend distance inside the box to zero at the sides of the box
+    float probe_weight(vec3 position, vec3 box_min, vec3 box_max, float blend_distance)
+    {
+        vec3 inside = min(position - box_min, box_max - position);
+        float distance = min(min(inside.x, inside.y), inside.z);
+        return clamp(distance / max(blend_distance, 0.0001), 0.0, 1.0);
+    }
+
+    // Returns the direction from the probe to the point where a ray from the given position in the given direction leaves the box
+    vec3 box_projected_direction(vec3 direction, vec3 position, vec3 probe_position, vec3 box_min, vec3 box_max)
+    {
+        vec3 first = (box_max - position) / direction;
+        vec3 second = (box_min - position) / direction;
+        vec3 furthest = max(first, second);
+        float distance = min(min(furthest.x, furthest.y), furthest.z);
+        return position + direction * max(distance, 0.0) - probe_position;
+    }
+
+    #endif
+";
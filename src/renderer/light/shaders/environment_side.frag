@@ -0,0 +1,15 @@
+
+uniform samplerCube environmentMap;
+uniform vec3 direction;
+uniform vec3 up;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec3 right = cross(direction, up);
+    vec3 N = normalize(up * (uvs.y - 0.5) * 2.0 + right * (uvs.x - 0.5) * 2.0 + direction);
+    outColor = vec4(texture(environmentMap, N).rgb, 1.0);
+}
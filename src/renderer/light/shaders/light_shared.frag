@@ -18,6 +18,63 @@ vec3 fresnel_schlick_roughness(vec3 F0, float cosTheta, float roughness)
     return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(saturate(1.0 - cosTheta), 5.0);
 }
 
+// The parameters of the physically based rendering extensions of the surface that is lit.
+// They are set by the material before calling calculate_lighting and the default values disable the extensions.
+vec3 material_dielectric_f0 = vec3(0.04); // The reflectance at normal incidence of a non-metallic surface, given by the index of refraction and specular color
+float material_specular = 1.0; // The strength of the specular reflection of a non-metallic surface
+float material_clearcoat = 0.0;
+float material_clearcoat_roughness = 0.0;
+vec3 material_clearcoat_normal = vec3(0.0, 0.0, 1.0);
+vec3 material_sheen_color = vec3(0.0);
+float material_sheen_roughness = 0.0;
+float material_transmission = 0.0;
+vec3 material_ambient_factor = vec3(1.0); // Multiplied with the indirect light from ambient lights, hemisphere lights and reflection probes, for example to apply a lightmap
+
+// The reflectance at normal incidence
+vec3 surface_f0(vec3 surface_color, float metallic)
+{
+    return mix(material_dielectric_f0 * material_specular, surface_color, metallic);
+}
+
+// The reflectance at grazing angles
+float surface_f90(float metallic)
+{
+    return mix(material_specular, 1.0, metallic);
+}
+
+vec3 surface_fresnel(vec3 surface_color, float metallic, float cosTheta, float roughness)
+{
+    vec3 F0 = surface_f0(surface_color, metallic);
+    vec3 F90 = max(vec3(surface_f90(metallic) * (1.0 - roughness)), F0);
+    return F0 + (F90 - F0) * pow(saturate(1.0 - cosTheta), 5.0);
+}
+
+// The color of the diffuse reflection, which is reduced by the light transmitted through the surface
+vec3 surface_diffuse(vec3 surface_color, float metallic)
+{
+    return mix(surface_color, vec3(0.0), metallic) * (1.0 - material_transmission);
+}
+
+// The light reflected by the sheen layer when lit by the given irradiance from all directions,
+// roughly approximating the amount of light reflected by the sheen distribution
+vec3 sheen_reflection(vec3 irradiance)
+{
+    return material_sheen_color * irradiance * (0.3 - 0.2 * material_sheen_roughness);
+}
+
+// The fresnel factor of a clearcoat layer with an index of refraction of 1.5
+float clearcoat_fresnel(float cosTheta)
+{
+    return 0.04 + 0.96 * pow(saturate(1.0 - cosTheta), 5.0);
+}
+
+// Adds the light reflected in the clearcoat layer to the light reflected by the surface below, which is attenuated by the clearcoat
+vec3 add_clearcoat(vec3 color, vec3 clearcoat_color, vec3 view_direction)
+{
+    float NdV = max(0.001, dot(material_clearcoat_normal, view_direction));
+    return color * (1.0 - material_clearcoat * clearcoat_fresnel(NdV)) + material_clearcoat * clearcoat_color;
+}
+
 
 // following functions are copies of UE4
 // for computing cook-torrance specular lighting terms
@@ -59,6 +116,20 @@ float calculate_D(float roughness, float NdH) {
     return D;
 }
 
+// Charlie sheen distribution function
+float D_charlie(in float roughness, in float NdH)
+{
+    float inv_alpha = 1.0 / max(roughness * roughness, 0.0001);
+    float sin2 = 1.0 - NdH * NdH;
+    return (2.0 + inv_alpha) * pow(sin2, 0.5 * inv_alpha) / (2.0 * PI);
+}
+
+// Neubelt sheen visibility function
+float V_neubelt(in float NdV, in float NdL)
+{
+    return saturate(1.0 / (4.0 * (NdL + NdV - NdL * NdV)));
+}
+
 // Smith's Schlick-GGX geometry function
 float G_schlick(in float roughness, in float NdV, in float NdL)
 {
@@ -102,22 +173,18 @@ vec3 calculate_light(vec3 light_color, vec3 L, vec3 surface_color, vec3 V, vec3
     float NdL = max(0.001, dot(N, L));
     float NdV = max(0.001, dot(N, V));
 
-    // mix between metal and non-metal material, for non-metal
-    // constant base specular factor of 0.04 grey is used
-    vec3 F0 = mix(vec3(0.04), surface_color, metallic);
-
     vec3 specular;
     vec3 specular_fresnel;
     if(lightingModel == 1u) {
         // specular reflectance with PHONG
-        specular_fresnel = fresnel_schlick_roughness(F0, NdV, roughness);
+        specular_fresnel = surface_fresnel(surface_color, metallic, NdV, roughness);
         specular = phong_specular(V, L, N, specular_fresnel, roughness);
     }
     else {
         vec3 H = normalize(L + V);
         float NdH = max(0.001, dot(N, H));
         float HdV = max(0.001, dot(H, V));
-        specular_fresnel = fresnel_schlick_roughness(F0, HdV, roughness);
+        specular_fresnel = surface_fresnel(surface_color, metallic, HdV, roughness);
 
         if(lightingModel == 2u) {
             // specular reflectance with BLINN
@@ -131,10 +198,29 @@ vec3 calculate_light(vec3 light_color, vec3 L, vec3 surface_color, vec3 V, vec3
 
     // diffuse is common for any model
     vec3 diffuse_fresnel = 1.0 - specular_fresnel;
-    vec3 diffuse = diffuse_fresnel * mix(surface_color, vec3(0.0), metallic) / PI;
+    vec3 diffuse = diffuse_fresnel * surface_diffuse(surface_color, metallic) / PI;
+    vec3 color = (diffuse + specular) * NdL;
+
+    vec3 H = normalize(L + V);
+    if(any(greaterThan(material_sheen_color, vec3(0.0)))) {
+        // sheen layer reflecting light at grazing angles, for example on fabric
+        float NdH = max(0.0, dot(N, H));
+        color += material_sheen_color * D_charlie(material_sheen_roughness, NdH) * V_neubelt(NdV, NdL) * NdL;
+    }
+    if(material_clearcoat > 0.0) {
+        // clearcoat layer with a cook-torrance specular reflection
+        vec3 clearcoat_normal = material_clearcoat_normal;
+        float clearcoat_roughness = max(material_clearcoat_roughness, 0.03);
+        float clearcoat_NdL = max(0.001, dot(clearcoat_normal, L));
+        float clearcoat_NdV = max(0.001, dot(clearcoat_normal, V));
+        float clearcoat_NdH = max(0.001, dot(clearcoat_normal, H));
+        float clearcoat_specular = clearcoat_fresnel(max(0.001, dot(H, V)))
+            * D_GGX(clearcoat_roughness, clearcoat_NdH) * G_schlick(clearcoat_roughness, clearcoat_NdV, clearcoat_NdL) / (4.0 * clearcoat_NdV * clearcoat_NdL);
+        color = add_clearcoat(color, vec3(clearcoat_specular * clearcoat_NdL), V);
+    }
 
     // final result
-    return (diffuse + specular) * light_color * NdL;
+    return color * light_color;
 }
 
 vec3 attenuate(vec3 light_color, vec3 attenuation, float distance)
@@ -146,35 +232,131 @@ vec3 attenuate(vec3 light_color, vec3 attenuation, float distance)
     return light_color / max(1.0, att);
 }
 
-float is_visible(vec3 lightDirection, vec3 normal, sampler2D shadowMap, vec4 shadow_coord, vec2 offset)
+// Returns the relative intensity of a photometric profile in the given direction from a light shining in the light direction.
+// The profile is indexed by the vertical angle from the light direction (u) and the horizontal angle around the light direction,
+// measured counterclockwise from the up direction when looking in the light direction (v).
+float photometric_intensity(sampler2D profile, vec3 light_direction, vec3 up, vec3 direction)
 {
-    vec2 uv = (shadow_coord.xy + offset)/shadow_coord.w;
-    if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
-        return 1.0;
+    float vertical = acos(clamp(dot(direction, light_direction), -1.0, 1.0));
+    vec2 d = vec2(dot(direction, up), dot(direction, cross(up, light_direction)));
+    float horizontal = dot(d, d) > 0.0000001 ? atan(d.y, d.x) : 0.0;
+    return texture(profile, vec2(vertical / PI, horizontal / (2.0 * PI))).r;
+}
+
+const vec2 POISSON_DISK[4] = vec2[](
+    vec2( -0.94201624, -0.39906216 ),
+    vec2( 0.94558609, -0.76890725 ),
+    vec2( -0.094184101, -0.92938870 ),
+    vec2( 0.34495938, 0.29387760 )
+);
+
+const vec2 POISSON_DISK_16[16] = vec2[](
+    vec2( -0.94201624, -0.39906216 ),
+    vec2( 0.94558609, -0.76890725 ),
+    vec2( -0.094184101, -0.92938870 ),
+    vec2( 0.34495938, 0.29387760 ),
+    vec2( -0.91588581, 0.45771432 ),
+    vec2( -0.81544232, -0.87912464 ),
+    vec2( -0.38277543, 0.27676845 ),
+    vec2( 0.97484398, 0.75648379 ),
+    vec2( 0.44323325, -0.97511554 ),
+    vec2( 0.53742981, -0.47373420 ),
+    vec2( -0.26496911, -0.41893023 ),
+    vec2( 0.79197514, 0.19090188 ),
+    vec2( -0.24188840, 0.99706507 ),
+    vec2( -0.81409955, 0.91437590 ),
+    vec2( 0.19984126, 0.78641367 ),
+    vec2( 0.14383161, -0.14100790 )
+);
+
+// A rotation which is random for each fragment, used to trade banding for noise when filtering
+mat2 random_rotation()
+{
+    float angle = 2.0 * PI * fract(sin(dot(gl_FragCoord.xy, vec2(12.9898, 78.233))) * 43758.5453);
+    float c = cos(angle);
+    float s = sin(angle);
+    return mat2(c, s, -s, c);
+}
+
+// Returns the shadow map uv coordinates (xy) and the biased depth (z) of the given position
+// shadowBias.x: The constant depth bias
+// shadowBias.y: The angle-scaled depth bias
+// shadowBias.z: The slope-scaled depth bias
+// shadowBias.w: The normal offset distance
+vec3 shadow_coord(mat4 shadowMVP, vec3 position, vec3 normal, vec3 lightDirection, vec4 shadowBias)
+{
+    float cos_theta = dot(normal, lightDirection);
+    vec4 coord = shadowMVP * vec4(position + normal * shadowBias.w, 1.);
+    // Adjust shadow bias based on surface normal and light direction
+    float c = clamp(cos_theta, 0.001, 1.0);
+    float slope = min(sqrt(1.0 - c * c) / c, 10.0);
+    float bias = max(shadowBias.x, shadowBias.y * (1.0 - cos_theta)) + shadowBias.z * slope;
+    return vec3(coord.xy, coord.z - bias) / coord.w;
+}
+
+// Returns the fraction of the light passing through the transparent shadow casters in front of the given shadow coordinates (see shadow_coord).
+// The transmittance is only applied behind the closest transparent caster and is interpolated bilinearly between the four closest texels.
+vec3 shadow_transmittance(sampler2D transmittanceMap, sampler2D transmittanceDepthMap, vec3 shadow_coord)
+{
+    if(shadow_coord.x < 0.0 || shadow_coord.x > 1.0 || shadow_coord.y < 0.0 || shadow_coord.y > 1.0) {
+        return vec3(1.0);
     }
-    float shadow_cast_distance = texture(shadowMap, uv).x;
-    if(shadow_cast_distance > 0.999) {
+    ivec2 size = textureSize(transmittanceMap, 0);
+    vec2 texel = shadow_coord.xy * vec2(size) - 0.5;
+    ivec2 base = ivec2(floor(texel));
+    vec2 f = fract(texel);
+    vec3 transmittance = vec3(0.0);
+    for (int y = 0; y < 2; y++) {
+        for (int x = 0; x < 2; x++) {
+            ivec2 t = clamp(base + ivec2(x, y), ivec2(0), size - 1);
+            float weight = (x == 0 ? 1.0 - f.x : f.x) * (y == 0 ? 1.0 - f.y : f.y);
+            bool behind = texelFetch(transmittanceDepthMap, t, 0).x < shadow_coord.z;
+            transmittance += weight * (behind ? texelFetch(transmittanceMap, t, 0).rgb : vec3(1.0));
+        }
+    }
+    return transmittance;
+}
+
+// Returns the cascade which contains the given position based on the view depth or 4 if the position is outside all cascades
+int shadow_cascade(mat4 cascadeView, vec4 cascadeSplits, vec3 position)
+{
+    float depth = -(cascadeView * vec4(position, 1.0)).z;
+    int cascade = 0;
+    while (cascade < 4 && depth > cascadeSplits[cascade]) {
+        cascade++;
+    }
+    return cascade;
+}
+
+float is_visible(vec3 lightDirection, vec3 normal, samplerCube shadowMap, vec2 shadowRange, vec3 light_to_position)
+{
+    float shadow_cast_depth = texture(shadowMap, light_to_position).x;
+    if(shadow_cast_depth > 0.999) {
         return 1.0;
     }
+    // Convert the depth to the distance along the major axis which is the view depth of the cube map side
+    float z_near = shadowRange.x;
+    float z_far = shadowRange.y;
+    float shadow_cast_distance = 2.0 * z_near * z_far / (z_far + z_near - (2.0 * shadow_cast_depth - 1.0) * (z_far - z_near));
+    vec3 a = abs(light_to_position);
+    float true_distance = max(a.x, max(a.y, a.z));
     // Adjust shadow bias based on surface normal and light direction
-    float bias = max(0.05 * (1.0 - dot(normal, lightDirection)), 0.005);
-    float true_distance = (shadow_coord.z - bias)/shadow_coord.w;
-    return shadow_cast_distance > true_distance ? 1.0 : 0.0;
+    float bias = max(0.05 * (1.0 - dot(normal, lightDirection)), 0.005) * true_distance;
+    return shadow_cast_distance > true_distance - bias ? 1.0 : 0.0;
 }
 
-float calculate_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, vec3 position)
+float calculate_omnidirectional_shadow(vec3 lightDirection, vec3 normal, samplerCube shadowMap, vec2 shadowRange, vec3 lightPosition, vec3 position)
 {
-    vec4 shadow_coord = shadowMVP * vec4(position, 1.);
+    vec3 light_to_position = position - lightPosition;
+    vec3 up = abs(lightDirection.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, lightDirection));
+    vec3 bitangent = cross(lightDirection, tangent);
+    float offset_scale = 0.005 * length(light_to_position);
     float visibility = 0.0;
-    vec2 poissonDisk[4] = vec2[](
-                                 vec2( -0.94201624, -0.39906216 ),
-                                 vec2( 0.94558609, -0.76890725 ),
-                                 vec2( -0.094184101, -0.92938870 ),
-                                 vec2( 0.34495938, 0.29387760 )
-                                 );
     for (int i=0;i<4;i++)
     {
-        visibility += is_visible(lightDirection, normal, shadowMap, shadow_coord, poissonDisk[i] * 0.001f);
+        vec3 offset = (tangent * POISSON_DISK[i].x + bitangent * POISSON_DISK[i].y) * offset_scale;
+        visibility += is_visible(lightDirection, normal, shadowMap, shadowRange, light_to_position + offset);
     }
     return visibility * 0.25;
 }
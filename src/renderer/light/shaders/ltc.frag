@@ -0,0 +1,91 @@
+// Lighting from polygonal area lights using linearly transformed cosines (LTC),
+// see "Real-Time Polygonal-Light Shading with Linearly Transformed Cosines" by Heitz et al.
+
+const float LTC_LUT_SIZE = 64.0;
+
+// Returns the coordinates in the LTC lookup tables for the given surface
+vec2 ltc_uv(vec3 normal, vec3 view_direction, float roughness)
+{
+    float NdV = clamp(dot(normal, view_direction), 0.0, 1.0);
+    vec2 uv = vec2(roughness, sqrt(1.0 - NdV));
+    return uv * (LTC_LUT_SIZE - 1.0) / LTC_LUT_SIZE + 0.5 / LTC_LUT_SIZE;
+}
+
+// Returns the transformation from world space to the space of a clamped cosine distribution, ie. the inverse LTC matrix
+// multiplied with the tangent frame where the view direction is in the xz-plane.
+mat3 ltc_transform(vec4 ltc_matrix, vec3 normal, vec3 view_direction)
+{
+    vec3 T1 = view_direction - normal * dot(view_direction, normal);
+    if (dot(T1, T1) < 0.000001) {
+        T1 = cross(normal, abs(normal.x) < 0.9 ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0));
+    }
+    T1 = normalize(T1);
+    vec3 T2 = cross(normal, T1);
+    mat3 Minv = mat3(vec3(ltc_matrix.x, 0.0, ltc_matrix.y), vec3(0.0, 1.0, 0.0), vec3(ltc_matrix.z, 0.0, ltc_matrix.w));
+    return Minv * transpose(mat3(T1, T2, normal));
+}
+
+// Returns the vector form factor of the edge between the two given points on the unit sphere
+vec3 ltc_edge(vec3 v1, vec3 v2)
+{
+    float x = dot(v1, v2);
+    float y = abs(x);
+    // Rational polynomial approximation of theta / sin(theta) / 2PI
+    float a = 0.8543985 + (0.4965155 + 0.0145206 * y) * y;
+    float b = 3.4175940 + (4.1616724 + 3.4170040 * y) * y;
+    float v = a / b;
+    float theta_sintheta = x > 0.0 ? v : 0.5 * inversesqrt(max(1.0 - x * x, 1e-7)) - v;
+    return cross(v1, v2) * theta_sintheta;
+}
+
+// Returns the form factor of the polygon with the given vector form factor clipped to the upper hemisphere.
+// The polygon is approximated by a sphere with the same vector form factor to avoid clipping the polygon.
+float ltc_form_factor(vec3 vector_form_factor)
+{
+    float l = length(vector_form_factor);
+    return max((l * l + vector_form_factor.z) / (l + 1.0), 0.0);
+}
+
+// Returns the integral of the cosine distribution given by the transform over the rectangle with the given corners.
+// The corners should be in clockwise order when seen from the position.
+float ltc_evaluate_rectangle(mat3 transform, vec3 position, vec3 corners[4])
+{
+    vec3 v0 = normalize(transform * (corners[0] - position));
+    vec3 v1 = normalize(transform * (corners[1] - position));
+    vec3 v2 = normalize(transform * (corners[2] - position));
+    vec3 v3 = normalize(transform * (corners[3] - position));
+    vec3 vector_form_factor = ltc_edge(v0, v1) + ltc_edge(v1, v2) + ltc_edge(v2, v3) + ltc_edge(v3, v0);
+    return ltc_form_factor(vector_form_factor);
+}
+
+// Returns the integral of the cosine distribution given by the transform over the disk with the given center and the two given radius vectors.
+// The disk is approximated by a regular polygon and the radius vectors should be in clockwise order when seen from the position.
+float ltc_evaluate_disk(mat3 transform, vec3 position, vec3 center, vec3 radius_x, vec3 radius_y)
+{
+    const int SEGMENTS = 16;
+    vec3 c = transform * (center - position);
+    vec3 x = transform * radius_x;
+    vec3 y = transform * radius_y;
+    vec3 first = normalize(c + x);
+    vec3 previous = first;
+    vec3 vector_form_factor = vec3(0.0);
+    for (int i = 1; i < SEGMENTS; i++) {
+        float angle = 2.0 * PI * float(i) / float(SEGMENTS);
+        vec3 current = normalize(c + x * cos(angle) + y * sin(angle));
+        vector_form_factor += ltc_edge(previous, current);
+        previous = current;
+    }
+    vector_form_factor += ltc_edge(previous, first);
+    return ltc_form_factor(vector_form_factor);
+}
+
+// Returns the light reflected from an area light with the given radiance
+// and the given integrals of the diffuse (cosine) and specular (LTC) distributions over the area of the light.
+vec3 ltc_light(vec3 light_color, vec3 surface_color, float metallic, vec2 ltc_amplitude, float diffuse_integral, float specular_integral)
+{
+    vec3 F0 = surface_f0(surface_color, metallic);
+    vec3 specular_fresnel = F0 * ltc_amplitude.x + (surface_f90(metallic) - F0) * ltc_amplitude.y;
+    vec3 diffuse = surface_diffuse(surface_color, metallic) * diffuse_integral;
+    vec3 specular = specular_fresnel * specular_integral;
+    return (diffuse + specular) * light_color;
+}
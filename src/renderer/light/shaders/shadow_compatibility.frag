@@ -0,0 +1,17 @@
+// Shadow functions kept for custom materials written before the shadow filters were introduced.
+// They use the bias and filter which were used by the directional and spot lights before the shadow bias and filter could be specified.
+
+float is_visible(vec3 lightDirection, vec3 normal, sampler2D shadowMap, vec4 shadow_coord, vec2 offset)
+{
+    // Adjust shadow bias based on surface normal and light direction
+    float bias = max(0.05 * (1.0 - dot(normal, lightDirection)), 0.005);
+    return is_visible(shadowMap, 0.0, (shadow_coord.xy + offset) / shadow_coord.w, (shadow_coord.z - bias) / shadow_coord.w);
+}
+
+float calculate_shadow(vec3 lightDirection, vec3 normal, sampler2D shadowMap, mat4 shadowMVP, vec3 position)
+{
+    vec4 coord = shadowMVP * vec4(position, 1.);
+    // Adjust shadow bias based on surface normal and light direction
+    float bias = max(0.05 * (1.0 - dot(normal, lightDirection)), 0.005);
+    return shadow_filter_poisson(shadowMap, 0.0, vec3(coord.xy, coord.z - bias) / coord.w, vec4(0.001, 0.0, 0.0, 0.0));
+}
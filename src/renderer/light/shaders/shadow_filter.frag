@@ -0,0 +1,89 @@
+// Filtering of shadow maps.
+// This source is included once for each type of shadow map with the following macros defined:
+// SHADOW_MAP: The sampler type of the shadow map, for example sampler2D.
+// SHADOW_UV(uv, layer): The texture coordinates used when sampling the given layer of the shadow map at the given uv coordinates.
+// All filter functions take the shadow map, the layer, the shadow coordinates (see shadow_coord) and a set of filter parameters.
+
+float shadow_depth(SHADOW_MAP shadowMap, float layer, vec2 uv)
+{
+    return texture(shadowMap, SHADOW_UV(uv, layer)).x;
+}
+
+float is_visible(SHADOW_MAP shadowMap, float layer, vec2 uv, float depth)
+{
+    if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        return 1.0;
+    }
+    float shadow_cast_distance = shadow_depth(shadowMap, layer, uv);
+    if(shadow_cast_distance > 0.999) {
+        return 1.0;
+    }
+    return shadow_cast_distance > depth ? 1.0 : 0.0;
+}
+
+float shadow_filter_none(SHADOW_MAP shadowMap, float layer, vec3 shadow_coord, vec4 parameters)
+{
+    return is_visible(shadowMap, layer, shadow_coord.xy, shadow_coord.z);
+}
+
+// parameters.x: The kernel size in texels, clamped to [1..MAX_PCF_KERNEL_SIZE] (9)
+float shadow_filter_pcf(SHADOW_MAP shadowMap, float layer, vec3 shadow_coord, vec4 parameters)
+{
+    vec2 texel_size = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    int kernel_size = clamp(int(parameters.x), 1, 9);
+    float center = 0.5 * float(kernel_size - 1);
+    float visibility = 0.0;
+    for (int x = 0; x < kernel_size; x++) {
+        for (int y = 0; y < kernel_size; y++) {
+            vec2 offset = (vec2(float(x), float(y)) - center) * texel_size;
+            visibility += is_visible(shadowMap, layer, shadow_coord.xy + offset, shadow_coord.z);
+        }
+    }
+    return visibility / float(kernel_size * kernel_size);
+}
+
+// parameters.x: The radius of the disk in uv coordinates
+// parameters.y: Whether or not the disk is randomly rotated
+float shadow_filter_poisson(SHADOW_MAP shadowMap, float layer, vec3 shadow_coord, vec4 parameters)
+{
+    mat2 rotation = parameters.y > 0.5 ? random_rotation() : mat2(1.0);
+    float visibility = 0.0;
+    for (int i = 0; i < 4; i++) {
+        vec2 offset = rotation * POISSON_DISK[i] * parameters.x;
+        visibility += is_visible(shadowMap, layer, shadow_coord.xy + offset, shadow_coord.z);
+    }
+    return visibility * 0.25;
+}
+
+// parameters.x: The size of the light in uv coordinates
+float shadow_filter_pcss(SHADOW_MAP shadowMap, float layer, vec3 shadow_coord, vec4 parameters)
+{
+    float light_size = parameters.x;
+    mat2 rotation = random_rotation();
+
+    // Find the average depth of the occluders
+    float blocker_depth = 0.0;
+    float blocker_count = 0.0;
+    for (int i = 0; i < 16; i++) {
+        float depth = shadow_depth(shadowMap, layer, shadow_coord.xy + rotation * POISSON_DISK_16[i] * light_size);
+        if (depth < shadow_coord.z) {
+            blocker_depth += depth;
+            blocker_count += 1.0;
+        }
+    }
+    if (blocker_count < 1.0) {
+        return 1.0;
+    }
+    blocker_depth /= blocker_count;
+
+    // Estimate the size of the penumbra from the distance between occluder and receiver
+    vec2 texel_size = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    float penumbra = max(light_size * (shadow_coord.z - blocker_depth) / max(blocker_depth, 0.0001), texel_size.x);
+
+    float visibility = 0.0;
+    for (int i = 0; i < 16; i++) {
+        vec2 offset = rotation * POISSON_DISK_16[i] * penumbra;
+        visibility += is_visible(shadowMap, layer, shadow_coord.xy + offset, shadow_coord.z);
+    }
+    return visibility / 16.0;
+}
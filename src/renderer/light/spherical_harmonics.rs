@@ -0,0 +1,165 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// The light shining from an environment projected onto the first nine real spherical harmonics basis functions (bands 0 to 2).
+/// This is a compact representation of the low frequency part of the environment which is sufficient for calculating the diffuse lighting from the environment,
+/// see [AmbientLight::new_with_spherical_harmonics].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphericalHarmonics {
+    /// The coefficients of the radiance for each of the basis functions in the order `Y00, Y1-1, Y10, Y11, Y2-2, Y2-1, Y20, Y21, Y22`,
+    /// where the basis functions are evaluated for a direction `(x, y, z)` as
+    /// `0.282095`, `0.488603 y`, `0.488603 z`, `0.488603 x`, `1.092548 xy`, `1.092548 yz`, `0.315392 (3z² - 1)`, `1.092548 xz` and `0.546274 (x² - y²)`.
+    pub coefficients: [Vec3; 9],
+}
+
+impl SphericalHarmonics {
+    /// The size of the sides of the cube map used when projecting an environment map.
+    const SIDE_SIZE: u32 = 64;
+
+    ///
+    /// Constructs spherical harmonics from the given coefficients of the radiance, for example calculated offline.
+    /// See [SphericalHarmonics::coefficients] for the order of the coefficients.
+    ///
+    pub fn new(coefficients: [Vec3; 9]) -> Self {
+        Self { coefficients }
+    }
+
+    ///
+    /// Projects the given environment map onto spherical harmonics.
+    /// The environment map is resampled to a low resolution on the GPU and then projected on the CPU,
+    /// so this is much cheaper than computing the irradiance map in [Environment].
+    /// If the environment map has mip maps, they are used to avoid missing small and bright light sources like the sun.
+    ///
+    pub fn from_environment_map(context: &Context, environment_map: &TextureCubeMap) -> Self {
+        let mut side_texture = Texture2D::new_empty::<[f32; 4]>(
+            context,
+            Self::SIDE_SIZE,
+            Self::SIDE_SIZE,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let viewport = Viewport::new_at_origo(Self::SIDE_SIZE, Self::SIDE_SIZE);
+        let mut coefficients = [Vec3::zero(); 9];
+        let mut total_weight = 0.0;
+        for side in CubeMapSide::iter() {
+            let pixels = side_texture
+                .as_color_target(None)
+                .clear(ClearState::default())
+                .apply_screen_material(
+                    &EnvironmentSideMaterial {
+                        environment_map,
+                        side,
+                    },
+                    Camera::new_2d(viewport),
+                    &[],
+                )
+                .read::<[f32; 4]>();
+            let direction = side.direction();
+            let up = side.up();
+            let right = direction.cross(up);
+            let size = Self::SIDE_SIZE as usize;
+            for (index, pixel) in pixels.iter().enumerate() {
+                // The rows are read from the top, ie. starting with the largest uv coordinate
+                let x = 2.0 * ((index % size) as f32 + 0.5) / size as f32 - 1.0;
+                let y = 1.0 - 2.0 * ((index / size) as f32 + 0.5) / size as f32;
+                // The solid angle covered by the texel relative to a texel at the center of the side
+                let weight = (1.0 + x * x + y * y).powf(-1.5);
+                let basis = Self::basis((direction + right * x + up * y).normalize());
+                let radiance = vec3(pixel[0], pixel[1], pixel[2]);
+                for (coefficient, b) in coefficients.iter_mut().zip(basis) {
+                    *coefficient += radiance * b * weight;
+                }
+                total_weight += weight;
+            }
+        }
+        let scale = 4.0 * std::f32::consts::PI / total_weight;
+        Self::new(coefficients.map(|c| c * scale))
+    }
+
+    ///
+    /// Returns the irradiance from the environment onto a surface with the given normal divided by π,
+    /// ie. the light reflected from a white diffuse surface with the given normal.
+    ///
+    pub fn irradiance(&self, normal: Vec3) -> Vec3 {
+        Self::basis(normal.normalize())
+            .iter()
+            .zip(self.convolved_coefficients())
+            .map(|(b, c)| c * *b)
+            .sum()
+    }
+
+    ///
+    /// Returns the coefficients convolved with the clamped cosine lobe and divided by π.
+    ///
+    pub(crate) fn convolved_coefficients(&self) -> [Vec3; 9] {
+        const BAND_FACTORS: [f32; 9] = [
+            1.0,
+            2.0 / 3.0,
+            2.0 / 3.0,
+            2.0 / 3.0,
+            0.25,
+            0.25,
+            0.25,
+            0.25,
+            0.25,
+        ];
+        let mut coefficients = self.coefficients;
+        for (coefficient, factor) in coefficients.iter_mut().zip(BAND_FACTORS) {
+            *coefficient *= factor;
+        }
+        coefficients
+    }
+
+    fn basis(direction: Vec3) -> [f32; 9] {
+        let Vector3 { x, y, z } = direction;
+        [
+            0.282095,
+            0.488603 * y,
+            0.488603 * z,
+            0.488603 * x,
+            1.092548 * x * y,
+            1.092548 * y * z,
+            0.315392 * (3.0 * z * z - 1.0),
+            1.092548 * x * z,
+            0.546274 * (x * x - y * y),
+        ]
+    }
+}
+
+struct EnvironmentSideMaterial<'a> {
+    environment_map: &'a TextureCubeMap,
+    side: CubeMapSide,
+}
+
+impl Material for EnvironmentSideMaterial<'_> {
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        format!(
+            "{}{}",
+            include_str!("../../core/shared.frag"),
+            include_str!("shaders/environment_side.frag")
+        )
+    }
+
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::EnvironmentSideMaterial
+    }
+
+    fn use_uniforms(&self, program: &Program, _viewer: &dyn Viewer, _lights: &[&dyn Light]) {
+        program.use_texture_cube("environmentMap", self.environment_map);
+        program.use_uniform("direction", self.side.direction());
+        program.use_uniform("up", self.side.up());
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates::default()
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
@@ -4,12 +4,15 @@ use crate::renderer::*;
 
 ///
 /// A light which shines from the given position and in the given direction.
-/// The light will cast shadows if you [generate a shadow map](SpotLight::generate_shadow_map).
+/// The light will cast shadows if you [generate a shadow map](SpotLight::generate_shadow_map) or [update a cached shadow map](SpotLight::update_shadow_map).
+/// Transparent objects cast colored shadows if you [generate a shadow map with transmittance](SpotLight::generate_shadow_map_with_transmittance).
 ///
 pub struct SpotLight {
     context: Context,
     shadow_texture: Option<DepthTexture2D>,
     shadow_matrix: Mat4,
+    shadow_cache: ShadowCache,
+    transmittance_map: Option<TransmittanceMap>,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
@@ -22,6 +25,14 @@ pub struct SpotLight {
     pub cutoff: Radians,
     /// The [Attenuation] of the light.
     pub attenuation: Attenuation,
+    /// The filter used when sampling the shadow map which determines how soft the edges of the shadows are.
+    pub shadow_filter: ShadowFilter,
+    /// The [ShadowBias] used when rendering and sampling the shadow map.
+    pub shadow_bias: ShadowBias,
+    /// The photometric profile of the light, for example loaded from an [IesProfile].
+    /// If specified, the intensity of the light in each direction is the intensity of the light multiplied by the relative intensity of the profile in that direction,
+    /// instead of fading out towards the cutoff angle. The light is still only emitted within the cutoff angle.
+    pub photometric_profile: Option<PhotometricProfile>,
 }
 
 impl SpotLight {
@@ -45,6 +56,11 @@ impl SpotLight {
             cutoff: cutoff.into(),
             attenuation,
             shadow_matrix: Mat4::identity(),
+            shadow_cache: ShadowCache::default(),
+            transmittance_map: None,
+            shadow_filter: ShadowFilter::default(),
+            shadow_bias: ShadowBias::default(),
+            photometric_profile: None,
         }
     }
 
@@ -55,80 +71,233 @@ impl SpotLight {
     pub fn clear_shadow_map(&mut self) {
         self.shadow_texture = None;
         self.shadow_matrix = Mat4::identity();
+        self.shadow_cache = ShadowCache::default();
+        self.transmittance_map = None;
     }
 
     ///
     /// Generate a shadow map which is used to simulate shadows from the spot light onto the geometries given as input.
     /// It is recomended that the texture size is power of 2.
     /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size.
+    /// The shadow texture is reused if it already exists and has the given size.
     ///
     pub fn generate_shadow_map(
         &mut self,
         texture_size: u32,
         geometries: impl IntoIterator<Item = impl Geometry> + Clone,
     ) {
-        let position = self.position;
-        let target = position + self.direction.normalize();
-        let up = compute_up_direction(self.direction);
+        self.generate_shadow_map_internal(texture_size, geometries, geometry_depth_material);
+    }
+
+    ///
+    /// Generate a shadow map in the same way as [SpotLight::generate_shadow_map], except that each object is rendered using its [depth material](Object::depth_material),
+    /// see [DirectionalLight::generate_shadow_map_with_objects].
+    ///
+    pub fn generate_shadow_map_with_objects(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        self.generate_shadow_map_internal(texture_size, objects, object_depth_material);
+    }
+
+    fn generate_shadow_map_internal<G: Geometry>(
+        &mut self,
+        texture_size: u32,
+        geometries: impl IntoIterator<Item = G> + Clone,
+        depth_material: impl Fn(&G) -> Option<DepthMaterial>,
+    ) {
+        let shadow_camera = self.shadow_camera(
+            texture_size,
+            geometries.clone().into_iter().map(|g| g.aabb()),
+        );
+        self.shadow_matrix = shadow_matrix(&shadow_camera);
+
+        let mut shadow_texture =
+            reuse_shadow_texture(&self.context, self.shadow_texture.take(), texture_size);
+        render_shadow_map(
+            &self.context,
+            &shadow_camera,
+            shadow_texture.as_depth_target(),
+            geometries,
+            depth_material,
+            &self.shadow_bias,
+            None,
+        );
+        self.shadow_texture = Some(shadow_texture);
+        self.shadow_cache = ShadowCache::default();
+        self.transmittance_map = None;
+    }
+
+    ///
+    /// Generate a shadow map in the same way as [SpotLight::generate_shadow_map], except that transparent objects
+    /// with a [TransmittanceMaterial] are rendered into a transmittance map which filters the light reaching the surfaces behind them,
+    /// see [DirectionalLight::generate_shadow_map_with_transmittance].
+    ///
+    pub fn generate_shadow_map_with_transmittance(
+        &mut self,
+        texture_size: u32,
+        objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        let shadow_camera =
+            self.shadow_camera(texture_size, objects.clone().into_iter().map(|o| o.aabb()));
+        self.shadow_matrix = shadow_matrix(&shadow_camera);
+
+        let mut shadow_texture =
+            reuse_shadow_texture(&self.context, self.shadow_texture.take(), texture_size);
+        render_shadow_map(
+            &self.context,
+            &shadow_camera,
+            shadow_texture.as_depth_target(),
+            objects
+                .clone()
+                .into_iter()
+                .filter(|o| transmittance_material(o).is_none()),
+            object_depth_material,
+            &self.shadow_bias,
+            None,
+        );
+        self.transmittance_map = TransmittanceMap::render(
+            &self.context,
+            &shadow_camera,
+            self.transmittance_map.take(),
+            objects,
+            &self.shadow_bias,
+        );
+        self.shadow_texture = Some(shadow_texture);
+        self.shadow_cache = ShadowCache::default();
+    }
+
+    ///
+    /// Updates the shadow map in the same way as [SpotLight::generate_shadow_map], but only if something has changed since the last update.
+    /// The static geometries are rendered into a cached shadow map, which is reused as long as the static geometries, the light and the [ShadowBias] are unchanged,
+    /// and the dynamic geometries are rendered on top of a copy of the cached shadow map.
+    ///
+    /// Changes are detected by comparing the bounding box of each geometry with the last update,
+    /// so call [SpotLight::mark_static_shadow_casters_changed] or [SpotLight::mark_dynamic_shadow_casters_changed] before updating
+    /// to force the changed geometries to be rendered again after changes that do not affect any bounding box.
+    /// The near and far plane of the shadow map are fitted to both the static and dynamic geometries,
+    /// so the static geometries are also rendered again when a dynamic geometry moves closer to or further away from the light than all other geometries.
+    ///
+    pub fn update_shadow_map(
+        &mut self,
+        texture_size: u32,
+        static_geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+        dynamic_geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        self.update_shadow_map_internal(
+            texture_size,
+            static_geometries,
+            dynamic_geometries,
+            geometry_depth_material,
+            geometry_depth_material,
+        );
+    }
+
+    ///
+    /// Updates the shadow map in the same way as [SpotLight::update_shadow_map], except that each object is rendered using its [depth material](Object::depth_material),
+    /// see [DirectionalLight::generate_shadow_map_with_objects].
+    ///
+    pub fn update_shadow_map_with_objects(
+        &mut self,
+        texture_size: u32,
+        static_objects: impl IntoIterator<Item = impl Object> + Clone,
+        dynamic_objects: impl IntoIterator<Item = impl Object> + Clone,
+    ) {
+        self.update_shadow_map_internal(
+            texture_size,
+            static_objects,
+            dynamic_objects,
+            object_depth_material,
+            object_depth_material,
+        );
+    }
+
+    ///
+    /// Marks the static shadow casters as changed, so that both the static and the dynamic shadow casters are rendered again at the next [SpotLight::update_shadow_map].
+    /// Use this when a static shadow caster has changed without changing its bounding box, for example when it is rotated around its center or deformed.
+    ///
+    pub fn mark_static_shadow_casters_changed(&mut self) {
+        self.shadow_cache.mark_static_changed();
+    }
+
+    ///
+    /// Marks the dynamic shadow casters as changed, so that they are rendered again on top of the cached static shadow casters at the next [SpotLight::update_shadow_map].
+    /// Use this when a dynamic shadow caster has changed without changing its bounding box, for example when it is rotated around its center or deformed.
+    ///
+    pub fn mark_dynamic_shadow_casters_changed(&mut self) {
+        self.shadow_cache.mark_dynamic_changed();
+    }
 
-        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+    fn update_shadow_map_internal<S: Geometry, D: Geometry>(
+        &mut self,
+        texture_size: u32,
+        static_geometries: impl IntoIterator<Item = S> + Clone,
+        dynamic_geometries: impl IntoIterator<Item = D> + Clone,
+        static_depth_material: impl Fn(&S) -> Option<DepthMaterial>,
+        dynamic_depth_material: impl Fn(&D) -> Option<DepthMaterial>,
+    ) {
+        let shadow_camera = self.shadow_camera(
+            texture_size,
+            static_geometries
+                .clone()
+                .into_iter()
+                .map(|g| g.aabb())
+                .chain(dynamic_geometries.clone().into_iter().map(|g| g.aabb())),
+        );
+        self.shadow_matrix = shadow_matrix(&shadow_camera);
 
+        let mut shadow_texture =
+            reuse_shadow_texture(&self.context, self.shadow_texture.take(), texture_size);
+        self.shadow_cache.update(
+            &self.context,
+            &shadow_camera,
+            &mut shadow_texture,
+            static_geometries,
+            dynamic_geometries,
+            static_depth_material,
+            dynamic_depth_material,
+            &self.shadow_bias,
+        );
+        self.shadow_texture = Some(shadow_texture);
+        self.transmittance_map = None;
+    }
+
+    ///
+    /// Returns the camera used to render a shadow map which covers the given bounding boxes.
+    ///
+    fn shadow_camera(
+        &self,
+        texture_size: u32,
+        aabbs: impl IntoIterator<Item = AxisAlignedBoundingBox>,
+    ) -> Camera {
         let mut z_far = 0.0f32;
         let mut z_near = f32::MAX;
-        for geometry in geometries.clone() {
-            let aabb = geometry.aabb();
+        for aabb in aabbs {
             if !aabb.is_empty() {
                 z_far = z_far.max(aabb.distance_max(self.position));
                 z_near = z_near.min(aabb.distance(self.position));
             }
         }
 
-        let shadow_camera = Camera::new_perspective(
-            viewport,
-            position,
-            target,
-            up,
+        Camera::new_perspective(
+            Viewport::new_at_origo(texture_size, texture_size),
+            self.position,
+            self.position + self.direction.normalize(),
+            compute_up_direction(self.direction),
             self.cutoff,
             z_near.max(0.01),
             z_far,
-        );
-        self.shadow_matrix = shadow_matrix(&shadow_camera);
+        )
+    }
 
-        let mut shadow_texture = DepthTexture2D::new::<f32>(
-            &self.context,
-            texture_size,
-            texture_size,
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
-        );
-        let depth_material = DepthMaterial {
-            render_states: RenderStates {
-                write_mask: WriteMask::DEPTH,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-        let frustum = shadow_camera.frustum();
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write::<RendererError>(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| frustum.contains(g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
-                }
-                Ok(())
-            })
-            .unwrap();
-        self.shadow_texture = Some(shadow_texture);
+    ///
+    /// Returns the distance from the light beyond which the light has no visible effect, see [Attenuation::range].
+    ///
+    pub fn range(&self) -> f32 {
+        let color = self.color.to_linear_srgb();
+        self.attenuation
+            .range(self.intensity * color.x.max(color.y).max(color.z))
     }
 
     ///
@@ -141,11 +310,34 @@ impl SpotLight {
 
 impl Light for SpotLight {
     fn shader_source(&self, i: u32) -> String {
+        let (profile_uniforms, cone) = if self.photometric_profile.is_some() {
+            (
+                format!(
+                    "uniform sampler2D photometricProfile{};\nuniform vec3 photometricUp{};",
+                    i, i
+                ),
+                format!(
+                    "photometric_intensity(photometricProfile{}, normalize(direction{}), photometricUp{}, -light_direction)",
+                    i, i, i
+                ),
+            )
+        } else {
+            (
+                String::new(),
+                "(1.0 - smoothstep(0.75 * cutoff, cutoff, angle))".to_string(),
+            )
+        };
         if self.shadow_texture.is_some() {
+            let (transmittance_uniforms, transmittance) =
+                TransmittanceMap::shader_source(self.transmittance_map.as_ref(), i);
             format!(
                 "
+                    {profile_uniforms}
+                    {transmittance_uniforms}
                     uniform sampler2D shadowMap{};
                     uniform mat4 shadowMVP{};
+                    uniform vec4 shadowFilter{};
+                    uniform vec4 shadowBias{};
 
                     uniform vec3 color{};
                     uniform vec3 attenuation{};
@@ -165,16 +357,19 @@ impl Light for SpotLight {
                         if (angle < cutoff) {{
                             vec3 light_color = attenuate(color{}, attenuation{}, distance);
                             result = calculate_light(light_color, light_direction, surface_color, view_direction, normal,
-                                metallic, roughness) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
-                            result *= calculate_shadow(light_direction, normal, shadowMap{}, shadowMVP{}, position);
+                                metallic, roughness) * {cone};
+                            vec3 coord = shadow_coord(shadowMVP{}, position, normal, light_direction, shadowBias{});
+                            result *= {}{transmittance};
                         }}
                         return result;
                     }}
 
-                ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+                ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i,
+                self.shadow_filter.shader_call(&format!("shadowMap{}", i), "0.0", "coord", &format!("shadowFilter{}", i)))
         } else {
             format!(
                 "
+                    {profile_uniforms}
                     uniform vec3 color{};
                     uniform vec3 attenuation{};
                     uniform vec3 position{};
@@ -193,7 +388,7 @@ impl Light for SpotLight {
                         if (angle < cutoff) {{
                             vec3 light_color = attenuate(color{}, attenuation{}, distance);
                             result = calculate_light(light_color, light_direction, surface_color, view_direction, normal,
-                                metallic, roughness) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
+                                metallic, roughness) * {cone};
                         }}
                         return result;
                     }}
@@ -205,6 +400,14 @@ impl Light for SpotLight {
         if let Some(ref tex) = self.shadow_texture {
             program.use_depth_texture(&format!("shadowMap{}", i), tex);
             program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
+            program.use_uniform(&format!("shadowBias{}", i), self.shadow_bias.parameters());
+            program.use_uniform_if_required(
+                &format!("shadowFilter{}", i),
+                self.shadow_filter.parameters(),
+            );
+        }
+        if let Some(ref transmittance_map) = self.transmittance_map {
+            transmittance_map.use_uniforms(program, i);
         }
         program.use_uniform(
             &format!("color{}", i),
@@ -221,9 +424,27 @@ impl Light for SpotLight {
         program.use_uniform(&format!("position{}", i), self.position);
         program.use_uniform(&format!("direction{}", i), self.direction.normalize());
         program.use_uniform(&format!("cutoff{}", i), self.cutoff.0);
+        if let Some(ref profile) = self.photometric_profile {
+            let direction = self.direction.normalize();
+            let mut up = profile.up - direction * profile.up.dot(direction);
+            if up.magnitude2() < 0.0001 {
+                up = compute_up_direction(direction);
+            }
+            program.use_texture(&format!("photometricProfile{}", i), &profile.texture);
+            program.use_uniform(&format!("photometricUp{}", i), up.normalize());
+        }
     }
 
     fn id(&self) -> LightId {
-        LightId::SpotLight(self.shadow_texture.is_some())
+        LightId::SpotLight(
+            self.shadow_texture.is_some(),
+            self.photometric_profile.is_some(),
+            self.transmittance_map.is_some(),
+            if self.shadow_texture.is_some() {
+                self.shadow_filter
+            } else {
+                ShadowFilter::None
+            },
+        )
     }
 }
@@ -22,6 +22,15 @@ macro_rules! impl_material_body {
         fn id(&self) -> EffectMaterialId {
             self.$inner().id()
         }
+        fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+            self.$inner().transmittance_material()
+        }
+        fn depth_material(&self) -> Option<DepthMaterial> {
+            self.$inner().depth_material()
+        }
+        fn uses_extensions_layer(&self) -> bool {
+            self.$inner().uses_extensions_layer()
+        }
     };
 }
 
@@ -39,6 +48,10 @@ mod depth_material;
 #[doc(inline)]
 pub use depth_material::*;
 
+mod transmittance_material;
+#[doc(inline)]
+pub use transmittance_material::*;
+
 mod intersection_material;
 #[doc(inline)]
 pub use intersection_material::*;
@@ -47,6 +60,10 @@ mod normal_material;
 #[doc(inline)]
 pub use normal_material::*;
 
+mod matcap_material;
+#[doc(inline)]
+pub use matcap_material::*;
+
 mod orm_material;
 #[doc(inline)]
 pub use orm_material::*;
@@ -67,6 +84,18 @@ mod deferred_physical_material;
 #[doc(inline)]
 pub use deferred_physical_material::*;
 
+mod triplanar_physical_material;
+#[doc(inline)]
+pub use triplanar_physical_material::*;
+
+mod terrain_material;
+#[doc(inline)]
+pub use terrain_material::*;
+
+mod toon_material;
+#[doc(inline)]
+pub use toon_material::*;
+
 mod skybox_material;
 #[doc(inline)]
 pub(in crate::renderer) use skybox_material::*;
@@ -75,6 +104,14 @@ mod isosurface_material;
 #[doc(inline)]
 pub use isosurface_material::*;
 
+mod volume_material;
+#[doc(inline)]
+pub use volume_material::*;
+
+mod wireframe_material;
+#[doc(inline)]
+pub use wireframe_material::*;
+
 use std::{ops::Deref, sync::Arc};
 
 ///
@@ -185,6 +222,32 @@ pub trait Material {
     /// Returns the type of material.
     ///
     fn material_type(&self) -> MaterialType;
+
+    ///
+    /// Returns the material used to render the transmittance of this material when it casts colored shadows,
+    /// see for example [DirectionalLight::generate_shadow_map_with_transmittance].
+    /// Only used if this is a [MaterialType::Transparent] material. If `None` is returned, the material casts a solid shadow.
+    ///
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        None
+    }
+
+    ///
+    /// Returns the material used to render the depth of this material, for example into a shadow map, if it differs from a default [DepthMaterial].
+    /// This is the case for materials with an alpha cutout, where the fragments below the threshold should not cast shadows when using for example [DirectionalLight::generate_shadow_map_with_objects].
+    ///
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        None
+    }
+
+    ///
+    /// Returns whether this [MaterialType::Deferred] material writes parameters to the optional fourth layer of the geometry pass,
+    /// like the specular, clearcoat and sheen parameters of a [DeferredPhysicalMaterial].
+    /// The fourth layer is only allocated by for example [RenderTarget::render] when at least one of the deferred materials uses it.
+    ///
+    fn uses_extensions_layer(&self) -> bool {
+        false
+    }
 }
 
 ///
@@ -247,18 +310,77 @@ impl<T: Material> Material for std::sync::RwLock<T> {
     fn id(&self) -> EffectMaterialId {
         self.read().unwrap().id()
     }
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        self.read().unwrap().transmittance_material()
+    }
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        self.read().unwrap().depth_material()
+    }
+    fn uses_extensions_layer(&self) -> bool {
+        self.read().unwrap().uses_extensions_layer()
+    }
 }
 
 fn is_transparent(cpu_material: &CpuMaterial) -> bool {
-    cpu_material.albedo.a != 255
-        || cpu_material
-            .albedo_texture
-            .as_ref()
-            .map(|t| match &t.data {
-                TextureData::RgbaU8(data) => data.iter().any(|d| d[3] != 255),
-                TextureData::RgbaF16(data) => data.iter().any(|d| d[3] < f16::from_f32(0.99)),
-                TextureData::RgbaF32(data) => data.iter().any(|d| d[3] < 0.99),
-                _ => false,
-            })
-            .unwrap_or(false)
+    cpu_material.alpha_cutout.is_none()
+        && (cpu_material.albedo.a != 255
+            || cpu_material
+                .albedo_texture
+                .as_ref()
+                .map(|t| match &t.data {
+                    TextureData::RgbaU8(data) => data.iter().any(|d| d[3] != 255),
+                    TextureData::RgbaF16(data) => data.iter().any(|d| d[3] < f16::from_f32(0.99)),
+                    TextureData::RgbaF32(data) => data.iter().any(|d| d[3] < 0.99),
+                    _ => false,
+                })
+                .unwrap_or(false))
+}
+
+///
+/// Returns the depth material discarding the same fragments as a material with the given alpha cutout, color and texture.
+///
+fn alpha_cutout_depth_material(
+    alpha_cutout: Option<f32>,
+    color: Srgba,
+    texture: &Option<Texture2DRef>,
+) -> Option<DepthMaterial> {
+    alpha_cutout.map(|alpha_cutout| DepthMaterial {
+        // The alpha value of the color is moved to the threshold, since the depth material has no color
+        alpha_cutout: Some(alpha_cutout * 255.0 / color.a.max(1) as f32),
+        alpha_texture: texture.clone(),
+        ..Default::default()
+    })
+}
+
+///
+/// Returns the reflectance at normal incidence of a non-metallic surface with the given index of refraction and specular color.
+///
+fn dielectric_f0(index_of_refraction: f32, specular_color: Srgba) -> Vec3 {
+    let f0 = ((index_of_refraction - 1.0) / (index_of_refraction + 1.0)).powi(2);
+    let color = specular_color.to_linear_srgb().truncate() * f0;
+    vec3(color.x.min(1.0), color.y.min(1.0), color.z.min(1.0))
+}
+
+///
+/// Returns whether a material constructed from the given [CpuMaterial] is double sided.
+/// The [CpuMaterial] does not contain the double sided flag of a glTF material, so this always returns true,
+/// which means that the back faces are not culled and are lit using the flipped normal.
+///
+fn is_double_sided(_cpu_material: &CpuMaterial) -> bool {
+    // TODO: Use the double sided flag when three-d-asset adds it to CpuMaterial and reads it from glTF files
+    true
+}
+
+///
+/// Returns the given render states with back face culling if the material is single sided and the render states do not specify any culling.
+///
+fn face_culling(render_states: RenderStates, double_sided: bool) -> RenderStates {
+    if !double_sided && render_states.cull == Cull::None {
+        RenderStates {
+            cull: Cull::Back,
+            ..render_states
+        }
+    } else {
+        render_states
+    }
 }
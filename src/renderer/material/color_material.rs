@@ -5,7 +5,7 @@ use crate::renderer::*;
 /// A material that renders a [Geometry] in a color defined by multiplying a color with an optional texture and optional per vertex colors.
 /// This material is not affected by lights.
 ///
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct ColorMaterial {
     /// Base surface color.
     pub color: Srgba,
@@ -16,6 +16,13 @@ pub struct ColorMaterial {
     pub render_states: RenderStates,
     /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
     pub is_transparent: bool,
+    /// A threshold on the alpha value of the color, below which the pixels are discarded, for example to render foliage without sorting.
+    /// An opaque material with an alpha cutout is rendered together with the other opaque objects and the threshold is also used when rendering shadows using for example [DirectionalLight::generate_shadow_map_with_objects].
+    pub alpha_cutout: Option<f32>,
+    /// Whether both sides of the surface are visible. If false, the back faces are culled, unless the [Self::render_states] specify how to cull.
+    /// Defaults to true, in which case the back faces are not culled.
+    /// A [CpuMaterial] does not contain the double sided flag of a glTF material, so a material constructed from a [CpuMaterial] is also double sided.
+    pub double_sided: bool,
 }
 
 impl ColorMaterial {
@@ -50,6 +57,8 @@ impl ColorMaterial {
             color: cpu_material.albedo,
             texture,
             is_transparent: false,
+            alpha_cutout: cpu_material.alpha_cutout,
+            double_sided: super::is_double_sided(cpu_material),
             render_states: RenderStates::default(),
         }
     }
@@ -72,6 +81,8 @@ impl ColorMaterial {
             color: cpu_material.albedo,
             texture,
             is_transparent: true,
+            alpha_cutout: cpu_material.alpha_cutout,
+            double_sided: super::is_double_sided(cpu_material),
             render_states: RenderStates {
                 write_mask: WriteMask::COLOR,
                 blend: Blend::TRANSPARENCY,
@@ -87,6 +98,8 @@ impl ColorMaterial {
             texture: physical_material.albedo_texture.clone(),
             render_states: physical_material.render_states,
             is_transparent: physical_material.is_transparent,
+            alpha_cutout: physical_material.alpha_cutout,
+            double_sided: physical_material.double_sided,
         }
     }
 }
@@ -99,7 +112,7 @@ impl FromCpuMaterial for ColorMaterial {
 
 impl Material for ColorMaterial {
     fn id(&self) -> EffectMaterialId {
-        EffectMaterialId::ColorMaterial(self.texture.is_some())
+        EffectMaterialId::ColorMaterial(self.texture.is_some(), self.alpha_cutout.is_some())
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
@@ -107,6 +120,9 @@ impl Material for ColorMaterial {
         if self.texture.is_some() {
             shader.push_str("#define USE_TEXTURE\nin vec2 uvs;\n");
         }
+        if self.alpha_cutout.is_some() {
+            shader.push_str("#define ALPHACUT\n");
+        }
         shader.push_str(include_str!("../../core/shared.frag"));
         shader.push_str(ColorMapping::fragment_shader_source());
         shader.push_str(include_str!("shaders/color_material.frag"));
@@ -120,9 +136,12 @@ impl Material for ColorMaterial {
             program.use_uniform("textureTransformation", tex.transformation);
             program.use_texture("tex", tex);
         }
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
     }
     fn render_states(&self) -> RenderStates {
-        self.render_states
+        super::face_culling(self.render_states, self.double_sided)
     }
     fn material_type(&self) -> MaterialType {
         if self.is_transparent {
@@ -131,4 +150,26 @@ impl Material for ColorMaterial {
             MaterialType::Opaque
         }
     }
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        Some(TransmittanceMaterial {
+            alpha_cutout: self.alpha_cutout,
+            ..TransmittanceMaterial::new(self.color, self.texture.clone())
+        })
+    }
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        super::alpha_cutout_depth_material(self.alpha_cutout, self.color, &self.texture)
+    }
+}
+
+impl Default for ColorMaterial {
+    fn default() -> Self {
+        Self {
+            color: Srgba::default(),
+            texture: None,
+            render_states: RenderStates::default(),
+            is_transparent: false,
+            alpha_cutout: None,
+            double_sided: true,
+        }
+    }
 }
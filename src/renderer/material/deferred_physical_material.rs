@@ -8,8 +8,15 @@ use crate::renderer::*;
 /// The first stage renders geometry information to a [RenderTarget] and the second stage uses this render target to apply lighting based on the geometry information which means the expensive lighting calculations are only done once per pixel.
 /// The [RenderTarget::render], [ColorTarget::render] or [DepthTarget::render] methods all support the two stages required by this material, so just pass the [Object] with this material applied into one of these methods.
 /// However, it is not possible to use the [Object::render] method to render a [Geometry] with this material directly to the screen.
-/// Instead render the object into a [RenderTarget] consisting of a [Texture2DArray] with three RGBA u8 layers as color target and a [DepthTexture2D] as depth target.
+/// Instead render the object into a [RenderTarget] consisting of a [Texture2DArray] with four RGBA u8 layers as color target and a [DepthTexture2D] as depth target.
 /// Then call the [DeferredPhysicalMaterial::lighting_pass] method with these textures to render to the screen.
+/// The fourth layer contains the specular, clearcoat and sheen parameters and can be left out, in which case these parameters are ignored,
+/// so [RenderTarget::render] only allocates it when at least one of the materials uses these parameters (see [Material::uses_extensions_layer]).
+///
+/// Since the geometry information is limited to the four layers, the deferred rendering only approximates the forward rendering of a [PhysicalMaterial] with these parameters:
+/// The clearcoat layer uses the same normal as the surface below,
+/// the [specular color](DeferredPhysicalMaterial::specular_color) and the [sheen color](DeferredPhysicalMaterial::sheen_color) are reduced to their average intensity
+/// and transmission is not supported.
 ///
 #[derive(Clone)]
 pub struct DeferredPhysicalMaterial {
@@ -46,7 +53,28 @@ pub struct DeferredPhysicalMaterial {
     /// A threshold on the alpha value of the color as a workaround for transparency.
     /// If the alpha value of a pixel touched by an object with this material is less than the threshold, then that object is not contributing to the color of that pixel.
     /// On the other hand, if the alpha value is more than the threshold, then it is contributing fully to that pixel and thereby blocks out everything behind.
+    /// The threshold is also used when rendering shadows using for example [DirectionalLight::generate_shadow_map_with_objects].
     pub alpha_cutout: Option<f32>,
+    /// Whether both sides of the surface are visible, in which case the back faces are lit using the flipped normal and tangent frame.
+    /// If false, the back faces are culled, unless the [Self::render_states] specify how to cull.
+    /// Defaults to true, in which case the back faces are not culled.
+    /// A [CpuMaterial] does not contain the double sided flag of a glTF material, so a material constructed from a [CpuMaterial] is also double sided.
+    pub double_sided: bool,
+    /// The index of refraction of the surface, which determines how much light is reflected by a non-metallic surface.
+    pub index_of_refraction: f32,
+    /// A value in the range `[0..1]` specifying the strength of the specular reflection of a non-metallic surface.
+    pub specular: f32,
+    /// The color of the specular reflection of a non-metallic surface when seen straight on.
+    pub specular_color: Srgba,
+    /// A value in the range `[0..1]` specifying the strength of a clear reflective layer on top of the surface, for example the lacquer on car paint.
+    pub clearcoat: f32,
+    /// A value in the range `[0..1]` specifying how rough the clearcoat layer is.
+    pub clearcoat_roughness: f32,
+    /// The color of a layer reflecting light at grazing angles, for example on velvet and other fabrics.
+    /// Black means no sheen layer.
+    pub sheen_color: Srgba,
+    /// A value in the range `[0..1]` specifying how rough the sheen layer is.
+    pub sheen_roughness: f32,
 }
 
 impl DeferredPhysicalMaterial {
@@ -54,6 +82,8 @@ impl DeferredPhysicalMaterial {
     /// Constructs a new deferred physical material from a [CpuMaterial].
     /// If the input contains an [CpuMaterial::occlusion_metallic_roughness_texture], this texture is used for both
     /// [DeferredPhysicalMaterial::metallic_roughness_texture] and [DeferredPhysicalMaterial::occlusion_texture] while any [CpuMaterial::metallic_roughness_texture] or [CpuMaterial::occlusion_texture] are ignored.
+    /// The index of refraction is copied from the [CpuMaterial], while the clearcoat, sheen and specular parameters
+    /// are not available in a [CpuMaterial] and are set to their default values which disable them.
     ///
     pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
         let albedo_texture =
@@ -114,13 +144,22 @@ impl DeferredPhysicalMaterial {
             occlusion_strength: cpu_material.occlusion_strength,
             render_states: RenderStates::default(),
             alpha_cutout: cpu_material.alpha_cutout,
+            double_sided: super::is_double_sided(cpu_material),
             emissive: cpu_material.emissive,
             emissive_texture,
+            index_of_refraction: cpu_material.index_of_refraction,
+            specular: 1.0,
+            specular_color: Srgba::WHITE,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
         }
     }
 
     ///
     /// Constructs a deferred physical material from a physical material.
+    /// The transmission and clearcoat normal map of the physical material are not supported and therefore ignored.
     ///
     pub fn from_physical_material(physical_material: &PhysicalMaterial) -> Self {
         Self {
@@ -142,15 +181,24 @@ impl DeferredPhysicalMaterial {
             emissive: physical_material.emissive,
             emissive_texture: physical_material.emissive_texture.clone(),
             alpha_cutout: if physical_material.is_transparent {
-                Some(0.5)
+                Some(physical_material.alpha_cutout.unwrap_or(0.5))
             } else {
-                None
+                physical_material.alpha_cutout
             },
+            double_sided: physical_material.double_sided,
+            index_of_refraction: physical_material.index_of_refraction,
+            specular: physical_material.specular,
+            specular_color: physical_material.specular_color,
+            clearcoat: physical_material.clearcoat,
+            clearcoat_roughness: physical_material.clearcoat_roughness,
+            sheen_color: physical_material.sheen_color,
+            sheen_roughness: physical_material.sheen_roughness,
         }
     }
     ///
     /// The second stage of a deferred render call.
     /// Use the [Object::render] method to render the objects with this material into a [RenderTarget] and then call this method with these textures to render to the screen.
+    /// The color texture must be a [ColorTexture::Array] with the first three or all four layers of the geometry pass.
     /// See [DeferredPhysicalMaterial] for more information.
     ///
     pub fn lighting_pass(
@@ -177,6 +225,16 @@ impl FromCpuMaterial for DeferredPhysicalMaterial {
     }
 }
 
+impl DeferredPhysicalMaterial {
+    ///
+    /// Returns the reflectance at normal incidence of a non-metallic surface reduced to its average intensity.
+    ///
+    fn dielectric_f0(&self) -> f32 {
+        let dielectric_f0 = super::dielectric_f0(self.index_of_refraction, self.specular_color);
+        self.specular * (dielectric_f0.x + dielectric_f0.y + dielectric_f0.z) / 3.0
+    }
+}
+
 impl Material for DeferredPhysicalMaterial {
     fn id(&self) -> EffectMaterialId {
         EffectMaterialId::DeferredPhysicalMaterial(
@@ -186,6 +244,7 @@ impl Material for DeferredPhysicalMaterial {
             self.normal_texture.is_some(),
             self.emissive_texture.is_some(),
             self.alpha_cutout.is_some(),
+            self.double_sided,
         )
     }
 
@@ -196,7 +255,6 @@ impl Material for DeferredPhysicalMaterial {
             || self.normal_texture.is_some()
             || self.occlusion_texture.is_some()
             || self.emissive_texture.is_some()
-            || self.alpha_cutout.is_some()
         {
             output.push_str("in vec2 uvs;\n");
             if self.albedo_texture.is_some() {
@@ -214,15 +272,12 @@ impl Material for DeferredPhysicalMaterial {
             if self.emissive_texture.is_some() {
                 output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
             }
-            if self.alpha_cutout.is_some() {
-                output.push_str(
-                    format!(
-                        "#define ALPHACUT;\nfloat acut = {};",
-                        self.alpha_cutout.unwrap()
-                    )
-                    .as_str(),
-                );
-            }
+        }
+        if self.alpha_cutout.is_some() {
+            output.push_str("#define ALPHACUT;\n");
+        }
+        if self.double_sided {
+            output.push_str("#define DOUBLE_SIDED;\n");
         }
         output.push_str(include_str!("shaders/deferred_physical_material.frag"));
         output
@@ -232,6 +287,9 @@ impl Material for DeferredPhysicalMaterial {
         program.use_uniform("metallic", self.metallic);
         program.use_uniform("roughness", self.roughness);
         program.use_uniform("albedo", self.albedo.to_linear_srgb());
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
         program.use_uniform("emissive", self.emissive.to_linear_srgb());
         if let Some(ref texture) = self.albedo_texture {
             program.use_texture("albedoTexture", texture);
@@ -257,15 +315,37 @@ impl Material for DeferredPhysicalMaterial {
                 program.use_texture("emissiveTexture", texture);
             }
         }
+        program.use_uniform("dielectricF0", self.dielectric_f0());
+        program.use_uniform("clearcoat", self.clearcoat);
+        program.use_uniform("clearcoatRoughness", self.clearcoat_roughness);
+        let sheen_color = self.sheen_color.to_linear_srgb();
+        program.use_uniform(
+            "sheen",
+            (sheen_color.x + sheen_color.y + sheen_color.z) / 3.0,
+        );
+        program.use_uniform("sheenRoughness", self.sheen_roughness);
     }
 
     fn render_states(&self) -> RenderStates {
-        self.render_states
+        super::face_culling(self.render_states, self.double_sided)
     }
 
     fn material_type(&self) -> MaterialType {
         MaterialType::Deferred
     }
+
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        super::alpha_cutout_depth_material(self.alpha_cutout, self.albedo, &self.albedo_texture)
+    }
+
+    fn uses_extensions_layer(&self) -> bool {
+        // Without the fourth layer, the lighting pass uses a reflectance of 0.04 and no clearcoat or sheen
+        (self.dielectric_f0() - 0.04).abs() > 0.0001
+            || self.clearcoat > 0.0
+            || self.sheen_color.r > 0
+            || self.sheen_color.g > 0
+            || self.sheen_color.b > 0
+    }
 }
 
 impl Default for DeferredPhysicalMaterial {
@@ -283,8 +363,16 @@ impl Default for DeferredPhysicalMaterial {
             occlusion_strength: 1.0,
             render_states: RenderStates::default(),
             alpha_cutout: None,
+            double_sided: true,
             emissive: Srgba::BLACK,
             emissive_texture: None,
+            index_of_refraction: 1.5,
+            specular: 1.0,
+            specular_color: Srgba::WHITE,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
         }
     }
 }
@@ -13,6 +13,12 @@ pub struct DepthMaterial {
     pub max_distance: Option<f32>,
     /// Render states.
     pub render_states: RenderStates,
+    /// A threshold on the alpha value of the [Self::alpha_texture] multiplied with the per vertex colors.
+    /// If the alpha value is less than the threshold, the depth is not written for that pixel.
+    /// This is used to render the shadows of materials with an alpha cutout, see for example [PhysicalMaterial::alpha_cutout].
+    pub alpha_cutout: Option<f32>,
+    /// Texture with the alpha values which are compared to the [Self::alpha_cutout].
+    pub alpha_texture: Option<Texture2DRef>,
 }
 
 impl FromCpuMaterial for DepthMaterial {
@@ -23,11 +29,22 @@ impl FromCpuMaterial for DepthMaterial {
 
 impl Material for DepthMaterial {
     fn id(&self) -> EffectMaterialId {
-        EffectMaterialId::DepthMaterial
+        EffectMaterialId::DepthMaterial(
+            self.alpha_cutout.is_some(),
+            self.alpha_cutout.is_some() && self.alpha_texture.is_some(),
+        )
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
-        include_str!("shaders/depth_material.frag").to_string()
+        let mut shader = String::new();
+        if self.alpha_cutout.is_some() {
+            shader.push_str("#define ALPHACUT\n");
+            if self.alpha_texture.is_some() {
+                shader.push_str("#define USE_ALPHA_TEXTURE\nin vec2 uvs;\n");
+            }
+        }
+        shader.push_str(include_str!("shaders/depth_material.frag"));
+        shader
     }
 
     fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, _lights: &[&dyn Light]) {
@@ -40,6 +57,13 @@ impl Material for DepthMaterial {
             self.max_distance.unwrap_or_else(|| viewer.z_far()),
         );
         program.use_uniform("eye", viewer.position());
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+            if let Some(ref texture) = self.alpha_texture {
+                program.use_uniform("alphaTexTransform", texture.transformation);
+                program.use_texture("alphaTexture", texture);
+            }
+        }
     }
 
     fn render_states(&self) -> RenderStates {
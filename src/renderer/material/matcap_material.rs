@@ -0,0 +1,145 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// A material that shades a [Geometry] by looking up the normal, as seen from the viewer, in a matcap (material capture) texture,
+/// which typically is an image of a lit sphere. This is often used for sculpting and CAD previews.
+/// This material is not affected by lights, instead the lighting is baked into the matcap.
+///
+#[derive(Clone)]
+pub struct MatcapMaterial {
+    /// The matcap texture, where the center corresponds to a normal facing the viewer and the edges of the inscribed circle to normals perpendicular to the view direction.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub matcap: Texture2DRef,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_texture].
+    pub normal_scale: f32,
+    /// A tangent space normal map, also known as bump map.
+    pub normal_texture: Option<Texture2DRef>,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether both sides of the surface are visible, in which case the normals of the back faces are flipped.
+    /// If false, the back faces are culled, unless the [Self::render_states] specify how to cull.
+    pub double_sided: bool,
+}
+
+impl MatcapMaterial {
+    ///
+    /// Constructs a new matcap material from a [CpuMaterial] using the [default matcap](MatcapMaterial::default_matcap).
+    /// Only the normal map of the [CpuMaterial] is used.
+    ///
+    pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        let normal_texture = cpu_material
+            .normal_texture
+            .as_ref()
+            .map(|cpu_texture| Texture2DRef::from_cpu_texture(context, cpu_texture));
+        Self {
+            normal_scale: cpu_material.normal_scale,
+            normal_texture,
+            ..Self::from_matcap(Self::default_matcap(context))
+        }
+    }
+
+    ///
+    /// Constructs a new matcap material from the given matcap texture.
+    ///
+    pub fn from_matcap(matcap: impl Into<Texture2DRef>) -> Self {
+        Self {
+            matcap: matcap.into(),
+            normal_scale: 1.0,
+            normal_texture: None,
+            render_states: RenderStates::default(),
+            double_sided: true,
+        }
+    }
+
+    ///
+    /// Returns a matcap of a sphere of grey clay lit from the upper left, which is used when constructing a matcap material from a [CpuMaterial].
+    ///
+    pub fn default_matcap(context: &Context) -> Texture2DRef {
+        const SIZE: u32 = 128;
+        let key_light = vec3(-0.4, 0.6, 0.7).normalize();
+        let fill_light = vec3(0.6, -0.4, 0.7).normalize();
+        let mut data = Vec::with_capacity((SIZE * SIZE) as usize);
+        for row in 0..SIZE {
+            for column in 0..SIZE {
+                // The first row is the top of the sphere
+                let x = 2.0 * (column as f32 + 0.5) / SIZE as f32 - 1.0;
+                let y = 1.0 - 2.0 * (row as f32 + 0.5) / SIZE as f32;
+                let xy = vec2(x, y);
+                let xy = if xy.magnitude2() > 1.0 {
+                    xy.normalize()
+                } else {
+                    xy
+                };
+                let normal = vec3(xy.x, xy.y, (1.0 - xy.magnitude2()).max(0.0).sqrt());
+                // The reflection of the view direction, which is along the z-axis
+                let reflection = 2.0 * normal.z * normal - vec3(0.0, 0.0, 1.0);
+                let diffuse = 0.08
+                    + 0.75 * normal.dot(key_light).max(0.0)
+                    + 0.15 * normal.dot(fill_light).max(0.0);
+                let specular = 0.35 * reflection.dot(key_light).max(0.0).powi(24);
+                let color = vec3(0.62, 0.58, 0.54) * diffuse + vec3(specular, specular, specular);
+                data.push([
+                    (color.x.min(1.0) * 255.0) as u8,
+                    (color.y.min(1.0) * 255.0) as u8,
+                    (color.z.min(1.0) * 255.0) as u8,
+                    255,
+                ]);
+            }
+        }
+        Texture2DRef::from_cpu_texture(
+            context,
+            &CpuTexture {
+                name: "default matcap".to_owned(),
+                data: TextureData::RgbaU8(data),
+                width: SIZE,
+                height: SIZE,
+                wrap_s: Wrapping::ClampToEdge,
+                wrap_t: Wrapping::ClampToEdge,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+impl FromCpuMaterial for MatcapMaterial {
+    fn from_cpu_material(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        Self::new(context, cpu_material)
+    }
+}
+
+impl Material for MatcapMaterial {
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::MatcapMaterial(self.normal_texture.is_some(), self.double_sided)
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        let mut source = String::new();
+        if self.normal_texture.is_some() {
+            source.push_str("#define USE_TEXTURE\nin vec2 uvs;\nin vec3 tang;\nin vec3 bitang;\n");
+        }
+        if self.double_sided {
+            source.push_str("#define DOUBLE_SIDED\n");
+        }
+        source.push_str(ColorMapping::fragment_shader_source());
+        source.push_str(include_str!("shaders/matcap_material.frag"));
+        source
+    }
+
+    fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, _lights: &[&dyn Light]) {
+        viewer.color_mapping().use_uniforms(program);
+        program.use_uniform("view", viewer.view());
+        program.use_texture("matcap", &self.matcap);
+        if let Some(ref tex) = self.normal_texture {
+            program.use_uniform("normalScale", self.normal_scale);
+            program.use_uniform("textureTransformation", tex.transformation);
+            program.use_texture("normalTexture", tex);
+        }
+    }
+    fn render_states(&self) -> RenderStates {
+        super::face_culling(self.render_states, self.double_sided)
+    }
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
@@ -14,6 +14,11 @@ pub struct NormalMaterial {
     pub normal_texture: Option<Texture2DRef>,
     /// Render states.
     pub render_states: RenderStates,
+    /// Whether both sides of the surface are visible, in which case the normals of the back faces are flipped.
+    /// If false, the back faces are culled, unless the [Self::render_states] specify how to cull.
+    /// Defaults to true, in which case the back faces are not culled.
+    /// A [CpuMaterial] does not contain the double sided flag of a glTF material, so a material constructed from a [CpuMaterial] is also double sided.
+    pub double_sided: bool,
 }
 
 impl NormalMaterial {
@@ -27,6 +32,7 @@ impl NormalMaterial {
             normal_scale: cpu_material.normal_scale,
             normal_texture,
             render_states: RenderStates::default(),
+            double_sided: super::is_double_sided(cpu_material),
         }
     }
 
@@ -40,6 +46,7 @@ impl NormalMaterial {
                 blend: Blend::Disabled,
                 ..physical_material.render_states
             },
+            double_sided: physical_material.double_sided,
         }
     }
 }
@@ -52,7 +59,7 @@ impl FromCpuMaterial for NormalMaterial {
 
 impl Material for NormalMaterial {
     fn id(&self) -> EffectMaterialId {
-        EffectMaterialId::NormalMaterial(self.normal_texture.is_some())
+        EffectMaterialId::NormalMaterial(self.normal_texture.is_some(), self.double_sided)
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
@@ -60,6 +67,9 @@ impl Material for NormalMaterial {
         if self.normal_texture.is_some() {
             source.push_str("#define USE_TEXTURE\nin vec2 uvs;\nin vec3 tang;\nin vec3 bitang;\n");
         }
+        if self.double_sided {
+            source.push_str("#define DOUBLE_SIDED\n");
+        }
         source.push_str(include_str!("shaders/normal_material.frag"));
         source
     }
@@ -72,7 +82,7 @@ impl Material for NormalMaterial {
         }
     }
     fn render_states(&self) -> RenderStates {
-        self.render_states
+        super::face_culling(self.render_states, self.double_sided)
     }
     fn material_type(&self) -> MaterialType {
         MaterialType::Opaque
@@ -85,6 +95,7 @@ impl Default for NormalMaterial {
             normal_texture: None,
             normal_scale: 1.0,
             render_states: RenderStates::default(),
+            double_sided: true,
         }
     }
 }
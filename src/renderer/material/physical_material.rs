@@ -1,5 +1,18 @@
 use crate::core::*;
 use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// Specifies how the [PhysicalMaterial::lightmap_texture] is combined with the indirect light from the [AmbientLight], [HemisphereLight] and [ReflectionProbes] lights.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LightmapMode {
+    /// The indirect light from the lights is multiplied with the lightmap, for example to apply baked occlusion and color bleeding to image based lighting.
+    #[default]
+    Modulate,
+    /// The lightmap contains all of the indirect light, so the indirect light from the lights is ignored and the surface is lit diffusely by the lightmap instead.
+    Replace,
+}
 
 ///
 /// A physically-based material that renders a [Geometry] in an approximate correct physical manner based on Physically Based Rendering (PBR).
@@ -30,10 +43,41 @@ pub struct PhysicalMaterial {
     pub normal_scale: f32,
     /// A tangent space normal map, also known as bump map.
     pub normal_texture: Option<Texture2DRef>,
+    /// A height map, where the red channel specifies the height of the surface, which is used to offset the texture coordinates of all textures
+    /// depending on the view direction (parallax occlusion mapping), so that for example bricks and cobblestones seem to stick out of the surface.
+    /// Like a normal map, this requires that the [Geometry] has tangents.
+    pub height_texture: Option<Texture2DRef>,
+    /// The depth of the surface described by the [Self::height_texture], measured in texture coordinates.
+    pub height_scale: f32,
+    /// The number of steps along the view direction used to find the intersection with the surface described by the [Self::height_texture].
+    /// More steps give a more accurate result, especially at grazing angles, but are slower to render.
+    pub parallax_steps: u32,
+    /// A lightmap with baked indirect light, for example exported from a modelling tool, which is sampled using the second set of uv coordinates of the [Geometry]
+    /// (see [Mesh::set_uvs2]), since a lightmap must not be mapped onto several places on the surface like for example a tiled albedo texture.
+    /// Note that the second set of uv coordinates is not loaded from a [CpuMesh], so it must always be set explicitly.
+    /// How the lightmap is combined with the ambient lights is specified by the [Self::lightmap_mode].
+    /// The colors are assumed to be in linear sRGB (`RgbU8`) or HDR color space.
+    pub lightmap_texture: Option<Texture2DRef>,
+    /// A multiplier applied to the colors of the [Self::lightmap_texture].
+    pub lightmap_intensity: f32,
+    /// Whether the [Self::lightmap_texture] replaces the light from ambient lights or is multiplied with it.
+    pub lightmap_mode: LightmapMode,
+    /// If specified, the edges of the triangles are drawn on top of the shaded surface, see [Wireframe] for the requirements to the geometry.
+    pub wireframe: Option<Wireframe>,
     /// Render states.
     pub render_states: RenderStates,
     /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
     pub is_transparent: bool,
+    /// A threshold on the alpha value of the color as a faster alternative to transparency, for example for foliage and fences.
+    /// If the alpha value of a pixel touched by an object with this material is less than the threshold, then that object is not contributing to the color of that pixel.
+    /// On the other hand, if the alpha value is more than the threshold, then it is contributing fully to that pixel and thereby blocks out everything behind.
+    /// An opaque material with an alpha cutout is rendered together with the other opaque objects and the threshold is also used when rendering shadows using for example [DirectionalLight::generate_shadow_map_with_objects].
+    pub alpha_cutout: Option<f32>,
+    /// Whether both sides of the surface are visible, for example for leaves, cloth and paper, in which case the back faces are lit using the flipped normal and tangent frame.
+    /// If false, the back faces are culled, unless the [Self::render_states] specify how to cull.
+    /// Defaults to true, in which case the back faces are not culled.
+    /// A [CpuMaterial] does not contain the double sided flag of a glTF material, so a material constructed from a [CpuMaterial] is also double sided.
+    pub double_sided: bool,
     /// Color of light shining from an object.
     pub emissive: Srgba,
     /// Texture with color of light shining from an object.
@@ -41,6 +85,37 @@ pub struct PhysicalMaterial {
     pub emissive_texture: Option<Texture2DRef>,
     /// The lighting model used when rendering this material
     pub lighting_model: LightingModel,
+    /// The index of refraction of the surface, which determines how much light is reflected by a non-metallic surface.
+    pub index_of_refraction: f32,
+    /// A value in the range `[0..1]` specifying the strength of the specular reflection of a non-metallic surface.
+    pub specular: f32,
+    /// The color of the specular reflection of a non-metallic surface when seen straight on.
+    pub specular_color: Srgba,
+    /// A value in the range `[0..1]` specifying the strength of a clear reflective layer on top of the surface, for example the lacquer on car paint.
+    pub clearcoat: f32,
+    /// A value in the range `[0..1]` specifying how rough the clearcoat layer is.
+    pub clearcoat_roughness: f32,
+    /// A scalar multiplier applied to each normal vector of the [Self::clearcoat_normal_texture].
+    pub clearcoat_normal_scale: f32,
+    /// A tangent space normal map for the clearcoat layer.
+    /// If not specified, the clearcoat layer follows the geometry and not the [Self::normal_texture].
+    pub clearcoat_normal_texture: Option<Texture2DRef>,
+    /// The color of a layer reflecting light at grazing angles, for example on velvet and other fabrics.
+    /// Black means no sheen layer.
+    pub sheen_color: Srgba,
+    /// A value in the range `[0..1]` specifying how rough the sheen layer is.
+    pub sheen_roughness: f32,
+    /// A value in the range `[0..1]` specifying how much light is transmitted through the surface instead of being diffusely reflected, for example for glass.
+    /// The transmitted light is only visible if a [Self::transmission_background] is specified.
+    pub transmission: f32,
+    /// Texture containing the transmission factor, which is multiplied with the [Self::transmission] value in the shader.
+    /// The transmission values are sampled from the red channel.
+    pub transmission_texture: Option<Texture2DRef>,
+    /// The scene behind the transmissive surfaces, which is sampled in screen space to find the light transmitted through the surface.
+    /// This is typically the opaque objects rendered into a texture with the same size as the viewport before rendering the transmissive objects.
+    /// The colors should be linear, ie. rendered using a camera where [Camera::disable_tone_and_color_mapping] has been called,
+    /// and the texture should have mip maps to support blurring the transmitted light on rough surfaces.
+    pub transmission_background: Option<Arc<Texture2D>>,
 }
 
 impl PhysicalMaterial {
@@ -48,6 +123,8 @@ impl PhysicalMaterial {
     /// Constructs a new physical material from a [CpuMaterial].
     /// If the input contains an [CpuMaterial::occlusion_metallic_roughness_texture], this texture is used for both
     /// [PhysicalMaterial::metallic_roughness_texture] and [PhysicalMaterial::occlusion_texture] while any [CpuMaterial::metallic_roughness_texture] or [CpuMaterial::occlusion_texture] are ignored.
+    /// The index of refraction and transmission parameters are copied from the [CpuMaterial], while the clearcoat, sheen and specular parameters
+    /// are not available in a [CpuMaterial] and are set to their default values which disable them.
     /// Tries to infer whether this material is transparent or opaque from the alpha value of the albedo color and the alpha values in the albedo texture.
     /// Since this is not always correct, it is preferred to use [PhysicalMaterial::new_opaque] or [PhysicalMaterial::new_transparent].
     ///
@@ -115,6 +192,10 @@ impl PhysicalMaterial {
                     }
                     _ => Texture2DRef::from_cpu_texture(context, cpu_texture),
                 });
+        let transmission_texture = cpu_material
+            .transmission_texture
+            .as_ref()
+            .map(|cpu_texture| Texture2DRef::from_cpu_texture(context, cpu_texture));
         Self {
             name: cpu_material.name.clone(),
             albedo: cpu_material.albedo,
@@ -124,6 +205,13 @@ impl PhysicalMaterial {
             metallic_roughness_texture,
             normal_texture,
             normal_scale: cpu_material.normal_scale,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
+            lightmap_texture: None,
+            lightmap_intensity: 1.0,
+            lightmap_mode: LightmapMode::default(),
+            wireframe: None,
             occlusion_texture,
             occlusion_strength: cpu_material.occlusion_strength,
             render_states: if is_transparent {
@@ -136,9 +224,23 @@ impl PhysicalMaterial {
                 RenderStates::default()
             },
             is_transparent,
+            alpha_cutout: cpu_material.alpha_cutout,
+            double_sided: super::is_double_sided(cpu_material),
             emissive: cpu_material.emissive,
             emissive_texture,
             lighting_model: cpu_material.lighting_model,
+            index_of_refraction: cpu_material.index_of_refraction,
+            specular: 1.0,
+            specular_color: Srgba::WHITE,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            clearcoat_normal_scale: 1.0,
+            clearcoat_normal_texture: None,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
+            transmission: cpu_material.transmission,
+            transmission_texture,
+            transmission_background: None,
         }
     }
 }
@@ -157,6 +259,14 @@ impl Material for PhysicalMaterial {
             self.occlusion_texture.is_some(),
             self.normal_texture.is_some(),
             self.emissive_texture.is_some(),
+            self.clearcoat_normal_texture.is_some(),
+            self.transmission_background.is_some(),
+            self.transmission_background.is_some() && self.transmission_texture.is_some(),
+            self.alpha_cutout.is_some(),
+            self.double_sided,
+            self.height_texture.is_some(),
+            self.lightmap_texture.is_some(),
+            self.wireframe.is_some(),
         )
     }
 
@@ -167,8 +277,11 @@ impl Material for PhysicalMaterial {
             || self.normal_texture.is_some()
             || self.occlusion_texture.is_some()
             || self.emissive_texture.is_some()
+            || self.clearcoat_normal_texture.is_some()
+            || self.height_texture.is_some()
+            || (self.transmission_background.is_some() && self.transmission_texture.is_some())
         {
-            output.push_str("in vec2 uvs;\n");
+            output.push_str("#define USE_UVS;\nin vec2 uvs;\n");
             if self.albedo_texture.is_some() {
                 output.push_str("#define USE_ALBEDO_TEXTURE;\n");
             }
@@ -179,14 +292,45 @@ impl Material for PhysicalMaterial {
                 output.push_str("#define USE_OCCLUSION_TEXTURE;\n");
             }
             if self.normal_texture.is_some() {
-                output.push_str("#define USE_NORMAL_TEXTURE;\nin vec3 tang;\nin vec3 bitang;\n");
+                output.push_str("#define USE_NORMAL_TEXTURE;\n");
+            }
+            if self.clearcoat_normal_texture.is_some() {
+                output.push_str("#define USE_CLEARCOAT_NORMAL_TEXTURE;\n");
+            }
+            if self.height_texture.is_some() {
+                output.push_str("#define USE_HEIGHT_TEXTURE;\n");
+            }
+            if self.normal_texture.is_some()
+                || self.clearcoat_normal_texture.is_some()
+                || self.height_texture.is_some()
+            {
+                output.push_str("in vec3 tang;\nin vec3 bitang;\n");
             }
             if self.emissive_texture.is_some() {
                 output.push_str("#define USE_EMISSIVE_TEXTURE;\n");
             }
         }
+        if self.lightmap_texture.is_some() {
+            output.push_str("#define USE_LIGHTMAP_TEXTURE;\nin vec2 uvs2;\n");
+        }
+        if self.alpha_cutout.is_some() {
+            output.push_str("#define ALPHACUT;\n");
+        }
+        if self.double_sided {
+            output.push_str("#define DOUBLE_SIDED;\n");
+        }
+        if self.transmission_background.is_some() {
+            output.push_str("#define USE_TRANSMISSION;\n");
+            if self.transmission_texture.is_some() {
+                output.push_str("#define USE_TRANSMISSION_TEXTURE;\n");
+            }
+        }
         output.push_str(ToneMapping::fragment_shader_source());
         output.push_str(ColorMapping::fragment_shader_source());
+        if self.wireframe.is_some() {
+            output.push_str("#define USE_WIREFRAME;\n");
+            output.push_str(Wireframe::fragment_shader_source());
+        }
         output.push_str(include_str!("shaders/physical_material.frag"));
         output
     }
@@ -220,6 +364,32 @@ impl Material for PhysicalMaterial {
                 program.use_texture("occlusionTexture", texture);
             }
         }
+        if program.requires_uniform("heightTexture") {
+            if let Some(ref texture) = self.height_texture {
+                program.use_uniform("heightTexTransform", texture.transformation);
+                program.use_uniform("heightScale", self.height_scale);
+                program.use_uniform("parallaxSteps", self.parallax_steps.max(1) as i32);
+                program.use_texture("heightTexture", texture);
+            }
+        }
+        if program.requires_uniform("lightmapTexture") {
+            if let Some(ref texture) = self.lightmap_texture {
+                program.use_uniform("lightmapTexTransform", texture.transformation);
+                program.use_uniform("lightmapIntensity", self.lightmap_intensity);
+                program.use_uniform(
+                    "lightmapReplace",
+                    if self.lightmap_mode == LightmapMode::Replace {
+                        1.0f32
+                    } else {
+                        0.0f32
+                    },
+                );
+                program.use_texture("lightmapTexture", texture);
+            }
+        }
+        if let Some(ref wireframe) = self.wireframe {
+            wireframe.use_uniforms(program);
+        }
         if program.requires_uniform("normalTexture") {
             if let Some(ref texture) = self.normal_texture {
                 program.use_uniform("normalTexTransform", texture.transformation);
@@ -229,6 +399,9 @@ impl Material for PhysicalMaterial {
         }
 
         program.use_uniform("albedo", self.albedo.to_linear_srgb());
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
         program.use_uniform("emissive", self.emissive.to_linear_srgb());
         if program.requires_uniform("emissiveTexture") {
             if let Some(ref texture) = self.emissive_texture {
@@ -236,10 +409,39 @@ impl Material for PhysicalMaterial {
                 program.use_texture("emissiveTexture", texture);
             }
         }
+
+        program.use_uniform_if_required(
+            "dielectricF0",
+            super::dielectric_f0(self.index_of_refraction, self.specular_color),
+        );
+        program.use_uniform_if_required("specular", self.specular);
+        program.use_uniform_if_required("clearcoat", self.clearcoat);
+        program.use_uniform_if_required("clearcoatRoughness", self.clearcoat_roughness);
+        if program.requires_uniform("clearcoatNormalTexture") {
+            if let Some(ref texture) = self.clearcoat_normal_texture {
+                program.use_uniform("clearcoatNormalTexTransform", texture.transformation);
+                program.use_uniform("clearcoatNormalScale", self.clearcoat_normal_scale);
+                program.use_texture("clearcoatNormalTexture", texture);
+            }
+        }
+        program.use_uniform_if_required("sheenColor", self.sheen_color.to_linear_srgb().truncate());
+        program.use_uniform_if_required("sheenRoughness", self.sheen_roughness);
+        if program.requires_uniform("transmissionBackground") {
+            if let Some(ref background) = self.transmission_background {
+                program.use_uniform("transmission", self.transmission);
+                program.use_uniform_if_required("indexOfRefraction", self.index_of_refraction);
+                program.use_uniform("viewProjection", viewer.projection() * viewer.view());
+                program.use_texture("transmissionBackground", background);
+                if let Some(ref texture) = self.transmission_texture {
+                    program.use_uniform("transmissionTexTransform", texture.transformation);
+                    program.use_texture("transmissionTexture", texture);
+                }
+            }
+        }
     }
 
     fn render_states(&self) -> RenderStates {
-        self.render_states
+        super::face_culling(self.render_states, self.double_sided)
     }
     fn material_type(&self) -> MaterialType {
         if self.is_transparent {
@@ -248,6 +450,15 @@ impl Material for PhysicalMaterial {
             MaterialType::Opaque
         }
     }
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        Some(TransmittanceMaterial {
+            alpha_cutout: self.alpha_cutout,
+            ..TransmittanceMaterial::new(self.albedo, self.albedo_texture.clone())
+        })
+    }
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        super::alpha_cutout_depth_material(self.alpha_cutout, self.albedo, &self.albedo_texture)
+    }
 }
 
 impl Default for PhysicalMaterial {
@@ -261,13 +472,34 @@ impl Default for PhysicalMaterial {
             metallic_roughness_texture: None,
             normal_texture: None,
             normal_scale: 1.0,
+            height_texture: None,
+            height_scale: 0.05,
+            parallax_steps: 16,
+            lightmap_texture: None,
+            lightmap_intensity: 1.0,
+            lightmap_mode: LightmapMode::default(),
+            wireframe: None,
             occlusion_texture: None,
             occlusion_strength: 1.0,
             render_states: RenderStates::default(),
             is_transparent: false,
+            alpha_cutout: None,
+            double_sided: true,
             emissive: Srgba::BLACK,
             emissive_texture: None,
             lighting_model: LightingModel::Blinn,
+            index_of_refraction: 1.5,
+            specular: 1.0,
+            specular_color: Srgba::WHITE,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            clearcoat_normal_scale: 1.0,
+            clearcoat_normal_texture: None,
+            sheen_color: Srgba::BLACK,
+            sheen_roughness: 0.0,
+            transmission: 0.0,
+            transmission_texture: None,
+            transmission_background: None,
         }
     }
 }
@@ -1,4 +1,7 @@
 uniform vec4 surfaceColor;
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#endif
 
 #ifdef USE_TEXTURE
 uniform sampler2D tex;
@@ -17,5 +20,9 @@ void main()
     outColor *= texture(tex, (textureTransformation * vec3(uvs, 1.0)).xy);
     #endif
 
+    #ifdef ALPHACUT
+    if (outColor.a < alphaCutout) discard;
+    #endif
+
     outColor.rgb = color_mapping(outColor.rgb);
 }
\ No newline at end of file
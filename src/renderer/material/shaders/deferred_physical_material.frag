@@ -3,6 +3,9 @@ uniform float metallic;
 uniform float roughness;
 
 uniform vec4 albedo;
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#endif
 #ifdef USE_ALBEDO_TEXTURE
 uniform sampler2D albedoTexture;
 uniform mat3 albedoTexTransform;
@@ -31,6 +34,12 @@ uniform mat3 normalTexTransform;
 uniform float normalScale;
 #endif
 
+uniform float dielectricF0;
+uniform float clearcoat;
+uniform float clearcoatRoughness;
+uniform float sheen;
+uniform float sheenRoughness;
+
 in vec3 pos;
 in vec3 nor;
 in vec4 col;
@@ -38,16 +47,16 @@ in vec4 col;
 layout (location = 0) out vec4 outColor;
 layout (location = 1) out vec4 outNormal;
 layout (location = 2) out vec4 outEmissive;
+layout (location = 3) out vec4 outExtensions;
 
 void main()
 {
     vec4 surface_color = albedo * col;
 #ifdef USE_ALBEDO_TEXTURE
-    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
-    #ifdef ALPHACUT
-        if (c.a < acut) discard;
-    #endif
-    surface_color *= c;
+    surface_color *= texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
+#endif
+#ifdef ALPHACUT
+    if (surface_color.a < alphaCutout) discard;
 #endif
 
     float metallic_factor = metallic;
@@ -63,10 +72,16 @@ void main()
     occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(uvs, 1.0)).xy).r, occlusionStrength);
 #endif
 
-    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#ifdef DOUBLE_SIDED
+    // The back faces are the other side of the surface
+    bool front_facing = gl_FrontFacing;
+#else
+    bool front_facing = true;
+#endif
+    vec3 normal = normalize(front_facing ? nor : -nor);
 #ifdef USE_NORMAL_TEXTURE
-    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
-    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    vec3 tangent = normalize(front_facing ? tang : -tang);
+    vec3 bitangent = normalize(front_facing ? bitang : -bitang);
     mat3 tbn = mat3(tangent, bitangent, normal);
     normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
 #endif
@@ -83,5 +98,6 @@ void main()
         nz = 0;
     }
     outNormal = vec4(0.5 * normal.xy + 0.5, float(o | nz << 7)/255.0, roughness_factor);
-    outEmissive = vec4(total_emissive, 0.0);
+    outEmissive = vec4(total_emissive, clearcoat);
+    outExtensions = vec4(sqrt(dielectricF0), clearcoatRoughness, sheen, sheenRoughness);
 }
\ No newline at end of file
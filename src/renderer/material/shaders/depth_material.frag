@@ -3,12 +3,28 @@ uniform vec3 eye;
 uniform float minDistance;
 uniform float maxDistance;
 
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#ifdef USE_ALPHA_TEXTURE
+uniform sampler2D alphaTexture;
+uniform mat3 alphaTexTransform;
+#endif
+in vec4 col;
+#endif
+
 in vec3 pos;
 
 layout (location = 0) out vec4 outColor;
 
 void main()
 {
+#ifdef ALPHACUT
+    float alpha = col.a;
+#ifdef USE_ALPHA_TEXTURE
+    alpha *= texture(alphaTexture, (alphaTexTransform * vec3(uvs, 1.0)).xy).a;
+#endif
+    if (alpha < alphaCutout) discard;
+#endif
     float dist = (distance(pos, eye) - minDistance) / (maxDistance - minDistance);
     outColor = vec4(dist, dist, dist, 1.0);
 }
\ No newline at end of file
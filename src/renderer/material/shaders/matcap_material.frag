@@ -0,0 +1,42 @@
+
+uniform mat4 view;
+uniform sampler2D matcap;
+
+#ifdef USE_TEXTURE
+uniform sampler2D normalTexture;
+uniform mat3 textureTransformation;
+uniform float normalScale;
+#endif
+
+in vec3 pos;
+in vec3 nor;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+#ifdef DOUBLE_SIDED
+    // The back faces are the other side of the surface
+    bool front_facing = gl_FrontFacing;
+#else
+    bool front_facing = true;
+#endif
+    vec3 normal = normalize(front_facing ? nor : -nor);
+#ifdef USE_TEXTURE
+    vec3 tangent = normalize(front_facing ? tang : -tang);
+    vec3 bitangent = normalize(front_facing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+    normal = normalize(tbn * ((2.0 * texture(normalTexture, (textureTransformation * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0)));
+#endif
+
+    // The normal is expressed in a frame facing the fragment instead of the camera,
+    // so that the shading does not change when the object moves towards the sides of a perspective view
+    vec3 view_normal = normalize(mat3(view) * normal);
+    vec3 view_direction = normalize((view * vec4(pos, 1.0)).xyz);
+    vec3 x = normalize(vec3(-view_direction.z, 0.0, view_direction.x));
+    vec3 y = cross(-view_direction, x);
+    vec2 uv = vec2(0.5 + 0.495 * dot(x, view_normal), 0.5 + 0.495 * dot(y, view_normal));
+
+    outColor = texture(matcap, uv);
+    outColor.rgb = color_mapping(outColor.rgb);
+}
@@ -12,10 +12,16 @@ layout (location = 0) out vec4 outColor;
 void main()
 {
 
-    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#ifdef DOUBLE_SIDED
+    // The back faces are the other side of the surface
+    bool front_facing = gl_FrontFacing;
+#else
+    bool front_facing = true;
+#endif
+    vec3 normal = normalize(front_facing ? nor : -nor);
 #ifdef USE_TEXTURE
-    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
-    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
+    vec3 tangent = normalize(front_facing ? tang : -tang);
+    vec3 bitangent = normalize(front_facing ? bitang : -bitang);
     mat3 tbn = mat3(tangent, bitangent, normal);
     normal = tbn * ((2.0 * texture(normalTexture, (textureTransformation * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
 #endif
@@ -4,6 +4,9 @@ uniform float roughness;
 uniform vec3 cameraPosition;
 
 uniform vec4 albedo;
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#endif
 #ifdef USE_ALBEDO_TEXTURE
 uniform sampler2D albedoTexture;
 uniform mat3 albedoTexTransform;
@@ -32,51 +35,183 @@ uniform mat3 normalTexTransform;
 uniform float normalScale;
 #endif
 
+#ifdef USE_LIGHTMAP_TEXTURE
+uniform sampler2D lightmapTexture;
+uniform mat3 lightmapTexTransform;
+uniform float lightmapIntensity;
+uniform float lightmapReplace;
+#endif
+
+uniform vec3 dielectricF0;
+uniform float specular;
+uniform float clearcoat;
+uniform float clearcoatRoughness;
+#ifdef USE_CLEARCOAT_NORMAL_TEXTURE
+uniform sampler2D clearcoatNormalTexture;
+uniform mat3 clearcoatNormalTexTransform;
+uniform float clearcoatNormalScale;
+#endif
+uniform vec3 sheenColor;
+uniform float sheenRoughness;
+
+#ifdef USE_TRANSMISSION
+uniform float transmission;
+#ifdef USE_TRANSMISSION_TEXTURE
+uniform sampler2D transmissionTexture;
+uniform mat3 transmissionTexTransform;
+#endif
+uniform sampler2D transmissionBackground;
+uniform mat4 viewProjection;
+uniform float indexOfRefraction;
+#endif
+
 in vec3 pos;
 in vec3 nor;
 in vec4 col;
 
+#ifdef USE_HEIGHT_TEXTURE
+uniform sampler2D heightTexture;
+uniform mat3 heightTexTransform;
+uniform float heightScale;
+uniform int parallaxSteps;
+
+// Returns the depth below the top of the surface at the given texture coordinates
+float surface_depth(vec2 uv, vec2 dx, vec2 dy)
+{
+    return 1.0 - textureGrad(heightTexture, (heightTexTransform * vec3(uv, 1.0)).xy, dx, dy).r;
+}
+
+// Returns the texture coordinates where the view ray hits the surface described by the height texture,
+// which is found by stepping along the ray through layers of equal depth
+vec2 parallax_occlusion_mapping(vec2 uv, vec3 tangent, vec3 bitangent, vec3 normal)
+{
+    vec3 view_direction = normalize(cameraPosition - pos);
+    vec3 V = vec3(dot(view_direction, tangent), dot(view_direction, bitangent), dot(view_direction, normal));
+
+    // The texture coordinate derivatives are computed outside the loop, since the loop is not uniform control flow
+    vec2 dx = mat2(heightTexTransform) * dFdx(uv);
+    vec2 dy = mat2(heightTexTransform) * dFdy(uv);
+
+    float layer_depth = 1.0 / float(parallaxSteps);
+    vec2 delta = V.xy / max(V.z, 0.05) * heightScale * layer_depth;
+    float current_depth = 0.0;
+    vec2 current_uv = uv;
+    float depth = surface_depth(current_uv, dx, dy);
+    for (int i = 0; i < parallaxSteps && current_depth < depth; i++) {
+        current_uv -= delta;
+        depth = surface_depth(current_uv, dx, dy);
+        current_depth += layer_depth;
+    }
+
+    // Interpolate between the last two steps to find the intersection
+    vec2 previous_uv = current_uv + delta;
+    float after = depth - current_depth;
+    float before = surface_depth(previous_uv, dx, dy) - current_depth + layer_depth;
+    return mix(current_uv, previous_uv, after / (after - before));
+}
+#endif
+
 layout (location = 0) out vec4 outColor;
 
 void main()
 {
+#ifdef DOUBLE_SIDED
+    // The back faces are the other side of the surface
+    bool front_facing = gl_FrontFacing;
+#else
+    bool front_facing = true;
+#endif
+    vec3 normal = normalize(front_facing ? nor : -nor);
+#if defined(USE_NORMAL_TEXTURE) || defined(USE_CLEARCOAT_NORMAL_TEXTURE) || defined(USE_HEIGHT_TEXTURE)
+    vec3 tangent = normalize(front_facing ? tang : -tang);
+    vec3 bitangent = normalize(front_facing ? bitang : -bitang);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+#endif
+#ifdef USE_UVS
+    vec2 tex_uvs = uvs;
+#ifdef USE_HEIGHT_TEXTURE
+    // All of the textures are sampled at the point where the view ray hits the displaced surface
+    tex_uvs = parallax_occlusion_mapping(uvs, tangent, bitangent, normal);
+#endif
+#endif
+
     vec4 surface_color = albedo * col;
 #ifdef USE_ALBEDO_TEXTURE
-    vec4 c = texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
-    #ifdef ALPHACUT
-        if (c.a < acut) discard;
-    #endif
-    surface_color *= c;
+    surface_color *= texture(albedoTexture, (albedoTexTransform * vec3(tex_uvs, 1.0)).xy);
+#endif
+#ifdef ALPHACUT
+    if (surface_color.a < alphaCutout) discard;
 #endif
 
     float metallic_factor = metallic;
     float roughness_factor = roughness;
 #ifdef USE_METALLIC_ROUGHNESS_TEXTURE
-    vec2 t = texture(metallicRoughnessTexture, (metallicRoughnessTexTransform * vec3(uvs, 1.0)).xy).gb;
+    vec2 t = texture(metallicRoughnessTexture, (metallicRoughnessTexTransform * vec3(tex_uvs, 1.0)).xy).gb;
     roughness_factor *= t.x;
     metallic_factor *= t.y;
 #endif
 
     float occlusion = 1.0;
 #ifdef USE_OCCLUSION_TEXTURE
-    occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(uvs, 1.0)).xy).r, occlusionStrength);
+    occlusion = mix(1.0, texture(occlusionTexture, (occlusionTexTransform * vec3(tex_uvs, 1.0)).xy).r, occlusionStrength);
 #endif
 
-    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+    material_clearcoat_normal = normal;
+#ifdef USE_CLEARCOAT_NORMAL_TEXTURE
+    material_clearcoat_normal = normalize(tbn * ((2.0 * texture(clearcoatNormalTexture, (clearcoatNormalTexTransform * vec3(tex_uvs, 1.0)).xy).xyz - 1.0) * vec3(clearcoatNormalScale, clearcoatNormalScale, 1.0)));
+#endif
 #ifdef USE_NORMAL_TEXTURE
-    vec3 tangent = normalize(gl_FrontFacing ? tang : -tang);
-    vec3 bitangent = normalize(gl_FrontFacing ? bitang : -bitang);
-    mat3 tbn = mat3(tangent, bitangent, normal);
-    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
+    normal = tbn * ((2.0 * texture(normalTexture, (normalTexTransform * vec3(tex_uvs, 1.0)).xy).xyz - 1.0) * vec3(normalScale, normalScale, 1.0));
+#endif
+
+    material_dielectric_f0 = dielectricF0;
+    material_specular = specular;
+    material_clearcoat = clearcoat;
+    material_clearcoat_roughness = clearcoatRoughness;
+    material_sheen_color = sheenColor;
+    material_sheen_roughness = sheenRoughness;
+
+#ifdef USE_TRANSMISSION
+    material_transmission = transmission;
+#ifdef USE_TRANSMISSION_TEXTURE
+    material_transmission *= texture(transmissionTexture, (transmissionTexTransform * vec3(tex_uvs, 1.0)).xy).r;
+#endif
+    material_transmission *= 1.0 - metallic_factor;
+
+    // The surface is assumed to be thin, so the light behind the surface is seen without any refraction
+    // but blurred by the roughness, where a higher index of refraction gives a more blurry result
+    vec4 clip_position = viewProjection * vec4(pos, 1.0);
+    vec2 screen_uv = 0.5 + 0.5 * clip_position.xy / clip_position.w;
+    float lod = log2(float(textureSize(transmissionBackground, 0).x)) * roughness_factor * clamp(2.0 * indexOfRefraction - 2.0, 0.0, 1.0);
+    vec3 background = textureLod(transmissionBackground, screen_uv, lod).rgb;
+    vec3 view_direction = normalize(cameraPosition - pos);
+    vec3 transmission_fresnel = surface_fresnel(surface_color.rgb, metallic_factor, max(0.001, dot(normal, view_direction)), roughness_factor);
+    vec3 transmitted = (1.0 - transmission_fresnel) * material_transmission * surface_color.rgb * background;
+    if (material_clearcoat > 0.0) {
+        transmitted *= 1.0 - material_clearcoat * clearcoat_fresnel(max(0.001, dot(material_clearcoat_normal, view_direction)));
+    }
 #endif
 
     vec3 total_emissive = emissive.rgb;
 #ifdef USE_EMISSIVE_TEXTURE
-    total_emissive *= texture(emissiveTexture, (emissiveTexTransform * vec3(uvs, 1.0)).xy).rgb;
+    total_emissive *= texture(emissiveTexture, (emissiveTexTransform * vec3(tex_uvs, 1.0)).xy).rgb;
+#endif
+
+#ifdef USE_LIGHTMAP_TEXTURE
+    // The lightmap uses the second set of uv coordinates, which are not displaced by the height texture
+    vec3 lightmap = lightmapIntensity * texture(lightmapTexture, (lightmapTexTransform * vec3(uvs2, 1.0)).xy).rgb;
+    material_ambient_factor = mix(lightmap, vec3(0.0), lightmapReplace);
+    total_emissive += lightmapReplace * occlusion * lightmap * surface_diffuse(surface_color.rgb, metallic_factor);
 #endif
 
     outColor.rgb = total_emissive + calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
+#ifdef USE_TRANSMISSION
+    outColor.rgb += transmitted;
+#endif
     outColor.rgb = tone_mapping(outColor.rgb);
-    outColor.rgb = color_mapping(outColor.rgb);
     outColor.a = surface_color.a;
+#ifdef USE_WIREFRAME
+    outColor = add_wireframe(outColor);
+#endif
+    outColor.rgb = color_mapping(outColor.rgb);
 }
\ No newline at end of file
@@ -0,0 +1,118 @@
+
+uniform vec3 cameraPosition;
+
+uniform int layerCount;
+uniform vec4 layerColors[MAX_TERRAIN_LAYERS];
+uniform vec4 layerMaterials[MAX_TERRAIN_LAYERS];
+uniform vec4 layerRules[MAX_TERRAIN_LAYERS];
+uniform float heightBlend;
+uniform float slopeBlend;
+
+uniform sampler2DArray albedoTextures;
+
+#ifdef USE_NORMAL_TEXTURES
+uniform sampler2DArray normalTextures;
+uniform float normalScale;
+#endif
+
+#ifdef USE_SPLAT_MAPS
+uniform sampler2DArray splatMaps;
+uniform vec2 splatMapOrigin;
+uniform vec2 splatMapSize;
+#endif
+
+#ifdef USE_TRIPLANAR
+uniform float blendSharpness;
+#endif
+
+in vec3 pos;
+in vec3 nor;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+// The weights of the projections along the x, y and z axes
+vec3 projection_weights = vec3(0.0, 1.0, 0.0);
+
+// Samples the given layer of the texture array using the world position as texture coordinates
+vec4 sample_layer(sampler2DArray textures, int layer, float scale)
+{
+    vec3 p = pos * scale;
+#ifdef USE_TRIPLANAR
+    return projection_weights.x * texture(textures, vec3(p.zy, float(layer)))
+        + projection_weights.y * texture(textures, vec3(p.xz, float(layer)))
+        + projection_weights.z * texture(textures, vec3(p.xy, float(layer)));
+#else
+    return texture(textures, vec3(p.xz, float(layer)));
+#endif
+}
+
+#ifdef USE_NORMAL_TEXTURES
+vec3 tangent_space_normal(vec2 uv, int layer)
+{
+    vec3 n = 2.0 * texture(normalTextures, vec3(uv, float(layer))).xyz - 1.0;
+    return vec3(n.xy * normalScale, n.z);
+}
+
+// Returns the surface normal perturbed by the normal map of the given layer, where the tangent frame of each projection is aligned with the world axes
+vec3 sample_layer_normal(int layer, float scale, vec3 normal)
+{
+    vec3 p = pos * scale;
+    vec3 normal_y = tangent_space_normal(p.xz, layer);
+    normal_y = vec3(normal_y.xy + normal.xz, abs(normal_y.z) * normal.y).xzy;
+#ifdef USE_TRIPLANAR
+    vec3 normal_x = tangent_space_normal(p.zy, layer);
+    normal_x = vec3(normal_x.xy + normal.zy, abs(normal_x.z) * normal.x).zyx;
+    vec3 normal_z = tangent_space_normal(p.xy, layer);
+    normal_z = vec3(normal_z.xy + normal.xy, abs(normal_z.z) * normal.z);
+    return normalize(projection_weights.x * normal_x + projection_weights.y * normal_y + projection_weights.z * normal_z);
+#else
+    return normalize(normal_y);
+#endif
+}
+#endif
+
+void main()
+{
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+#ifdef USE_TRIPLANAR
+    projection_weights = pow(abs(normal), vec3(blendSharpness));
+    projection_weights /= projection_weights.x + projection_weights.y + projection_weights.z;
+#endif
+    // The angle between the surface and the horizontal plane
+    float slope = acos(clamp(normal.y, -1.0, 1.0));
+#ifdef USE_SPLAT_MAPS
+    vec2 splat_uv = (pos.xz - splatMapOrigin) / splatMapSize;
+#endif
+
+    // Each layer is painted on top of the previous layers, where the first layer covers the entire terrain
+    vec4 surface_color = vec4(0.0);
+    float metallic = 0.0;
+    float roughness = 1.0;
+    vec3 surface_normal = normal;
+    for (int i = 0; i < layerCount; i++) {
+        vec4 rules = layerRules[i];
+        float weight = clamp((pos.y - rules.x) / heightBlend + 0.5, 0.0, 1.0)
+            * clamp((rules.y - pos.y) / heightBlend + 0.5, 0.0, 1.0)
+            * clamp((slope - rules.z) / slopeBlend + 0.5, 0.0, 1.0)
+            * clamp((rules.w - slope) / slopeBlend + 0.5, 0.0, 1.0);
+#ifdef USE_SPLAT_MAPS
+        weight *= texture(splatMaps, vec3(splat_uv, float(i / 4)))[i % 4];
+#endif
+        weight = i == 0 ? 1.0 : weight;
+
+        vec4 parameters = layerMaterials[i];
+        surface_color = mix(surface_color, layerColors[i] * sample_layer(albedoTextures, i, parameters.z), weight);
+        metallic = mix(metallic, parameters.x, weight);
+        roughness = mix(roughness, parameters.y, weight);
+#ifdef USE_NORMAL_TEXTURES
+        surface_normal = normalize(mix(surface_normal, sample_layer_normal(i, parameters.z, normal), weight));
+#endif
+    }
+    surface_color *= col;
+
+    outColor.rgb = calculate_lighting(cameraPosition, surface_color.rgb, pos, surface_normal, metallic, roughness, 1.0);
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = surface_color.a;
+}
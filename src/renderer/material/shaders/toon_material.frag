@@ -0,0 +1,77 @@
+
+uniform vec3 cameraPosition;
+
+uniform vec4 albedo;
+#ifdef USE_ALBEDO_TEXTURE
+uniform sampler2D albedoTexture;
+uniform mat3 albedoTexTransform;
+#endif
+
+#ifdef USE_RAMP
+uniform sampler2D ramp;
+#else
+uniform float bands;
+#endif
+
+uniform float specularStrength;
+uniform float roughness;
+uniform vec3 rimColor;
+uniform float rimWidth;
+
+in vec3 pos;
+in vec3 nor;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+// Sums the quantised diffuse and specular light from all of the lights, defined after this shader
+void calculate_toon_lighting(vec3 position, vec3 normal, vec3 view_direction, out vec3 diffuse, out vec3 specular);
+
+// Maps the intensity of the light to the shade of the surface
+vec3 toon_shade(float intensity)
+{
+#ifdef USE_RAMP
+    return texture(ramp, vec2(saturate(intensity), 0.5)).rgb;
+#else
+    return vec3(floor(intensity * (bands - 1.0) + 0.5) / (bands - 1.0));
+#endif
+}
+
+// Quantises the light reflected diffusely from a white surface.
+// The light is scaled by PI, so that a light with an intensity of one shining directly onto the surface has an intensity of one.
+vec3 toon_diffuse(vec3 light)
+{
+    float intensity = PI * max(max(light.r, light.g), light.b);
+    return intensity > 0.0 ? light * PI / intensity * toon_shade(intensity) : vec3(0.0);
+}
+
+// Quantises the light reflected specularly from a white surface into a highlight where the reflection is brighter than the light
+vec3 toon_specular(vec3 light)
+{
+    float intensity = max(max(light.r, light.g), light.b);
+    return intensity > 1.0 ? light / intensity : vec3(0.0);
+}
+
+void main()
+{
+    vec4 surface_color = albedo * col;
+#ifdef USE_ALBEDO_TEXTURE
+    surface_color *= texture(albedoTexture, (albedoTexTransform * vec3(uvs, 1.0)).xy);
+#endif
+
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+    vec3 view_direction = normalize(cameraPosition - pos);
+
+    // No specular reflection when calculating the diffuse light
+    material_specular = 0.0;
+    vec3 diffuse;
+    vec3 specular;
+    calculate_toon_lighting(pos, normal, view_direction, diffuse, specular);
+
+    float rim = step(1.0 - rimWidth, 1.0 - max(0.0, dot(normal, view_direction)));
+
+    outColor.rgb = surface_color.rgb * diffuse + specularStrength * specular + rim * rimColor;
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = surface_color.a;
+}
@@ -0,0 +1,31 @@
+
+uniform vec4 surfaceColor;
+
+#ifdef USE_TEXTURE
+uniform sampler2D tex;
+uniform mat3 textureTransformation;
+#endif
+
+#ifdef ALPHACUT
+uniform float alphaCutout;
+#endif
+
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 color = surfaceColor * col;
+
+    #ifdef USE_TEXTURE
+    color *= texture(tex, (textureTransformation * vec3(uvs, 1.0)).xy);
+    #endif
+
+    if (color.a <= 0.0) discard;
+    #ifdef ALPHACUT
+    if (color.a < alphaCutout) discard;
+    #endif
+
+    outColor = vec4(mix(vec3(1.0), color.rgb, color.a), 1.0);
+}
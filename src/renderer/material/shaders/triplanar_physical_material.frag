@@ -0,0 +1,98 @@
+
+uniform float metallic;
+uniform float roughness;
+uniform vec3 cameraPosition;
+uniform vec4 albedo;
+uniform vec4 emissive;
+
+uniform vec3 textureScale;
+uniform float blendSharpness;
+
+#ifdef USE_ALBEDO_TEXTURE
+uniform sampler2D albedoTexture;
+uniform mat3 albedoTexTransform;
+#endif
+
+#ifdef USE_METALLIC_ROUGHNESS_TEXTURE
+uniform sampler2D metallicRoughnessTexture;
+uniform mat3 metallicRoughnessTexTransform;
+#endif
+
+#ifdef USE_OCCLUSION_TEXTURE
+uniform sampler2D occlusionTexture;
+uniform mat3 occlusionTexTransform;
+uniform float occlusionStrength;
+#endif
+
+#ifdef USE_NORMAL_TEXTURE
+uniform sampler2D normalTexture;
+uniform mat3 normalTexTransform;
+uniform float normalScale;
+#endif
+
+in vec3 pos;
+in vec3 nor;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+// The texture coordinates of the projections along the x, y and z axes
+vec2 triplanar_uvs[3];
+
+// Returns the weighted sum of the texture sampled in the projections along the x, y and z axes
+vec4 triplanar_texture(sampler2D tex, mat3 transformation, vec3 weights)
+{
+    return weights.x * texture(tex, (transformation * vec3(triplanar_uvs[0], 1.0)).xy)
+        + weights.y * texture(tex, (transformation * vec3(triplanar_uvs[1], 1.0)).xy)
+        + weights.z * texture(tex, (transformation * vec3(triplanar_uvs[2], 1.0)).xy);
+}
+
+void main()
+{
+    vec3 normal = normalize(gl_FrontFacing ? nor : -nor);
+
+    // The projections are blended by the normal, where a higher sharpness gives narrower transitions
+    vec3 weights = pow(abs(normal), vec3(blendSharpness));
+    weights /= weights.x + weights.y + weights.z;
+    vec3 p = pos * textureScale;
+    triplanar_uvs[0] = p.zy;
+    triplanar_uvs[1] = p.xz;
+    triplanar_uvs[2] = p.xy;
+
+    vec4 surface_color = albedo * col;
+#ifdef USE_ALBEDO_TEXTURE
+    surface_color *= triplanar_texture(albedoTexture, albedoTexTransform, weights);
+#endif
+
+    float metallic_factor = metallic;
+    float roughness_factor = roughness;
+#ifdef USE_METALLIC_ROUGHNESS_TEXTURE
+    vec2 t = triplanar_texture(metallicRoughnessTexture, metallicRoughnessTexTransform, weights).gb;
+    roughness_factor *= t.x;
+    metallic_factor *= t.y;
+#endif
+
+    float occlusion = 1.0;
+#ifdef USE_OCCLUSION_TEXTURE
+    occlusion = mix(1.0, triplanar_texture(occlusionTexture, occlusionTexTransform, weights).r, occlusionStrength);
+#endif
+
+#ifdef USE_NORMAL_TEXTURE
+    // Each projection has a tangent frame aligned with the world axes, so the tangent space normals are
+    // combined with the surface normal using a whiteout blend and swizzled into world space
+    vec3 normals[3];
+    for (int i = 0; i < 3; i++) {
+        normals[i] = 2.0 * texture(normalTexture, (normalTexTransform * vec3(triplanar_uvs[i], 1.0)).xy).xyz - 1.0;
+        normals[i].xy *= normalScale;
+    }
+    vec3 normal_x = vec3(normals[0].xy + normal.zy, abs(normals[0].z) * normal.x);
+    vec3 normal_y = vec3(normals[1].xy + normal.xz, abs(normals[1].z) * normal.y);
+    vec3 normal_z = vec3(normals[2].xy + normal.xy, abs(normals[2].z) * normal.z);
+    normal = normalize(weights.x * normal_x.zyx + weights.y * normal_y.xzy + weights.z * normal_z);
+#endif
+
+    outColor.rgb = emissive.rgb + calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic_factor, roughness_factor, occlusion);
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+    outColor.a = surface_color.a;
+}
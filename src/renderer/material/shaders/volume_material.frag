@@ -0,0 +1,93 @@
+uniform vec3 cameraPosition;
+uniform sampler3D voxels;
+uniform sampler2D transferFunction;
+uniform vec3 size;
+uniform vec3 h;
+uniform int stepCount;
+uniform float opacityScale;
+#ifdef USE_SHADING
+uniform float roughness;
+#endif
+
+in vec3 pos;
+
+layout (location = 0) out vec4 outColor;
+
+vec4 transfer_function(float value) {
+    // Sample between the centers of the first and last texel
+    float n = float(textureSize(transferFunction, 0).x);
+    return texture(transferFunction, vec2((clamp(value, 0.0, 1.0) * (n - 1.0) + 0.5) / n, 0.5));
+}
+
+#ifdef USE_SHADING
+vec3 estimate_gradient(vec3 uvw) {
+    float x = texture(voxels, uvw + vec3(h.x, 0.0, 0.0)).r - texture(voxels, uvw - vec3(h.x, 0.0, 0.0)).r;
+    float y = texture(voxels, uvw + vec3(0.0, h.y, 0.0)).r - texture(voxels, uvw - vec3(0.0, h.y, 0.0)).r;
+    float z = texture(voxels, uvw + vec3(0.0, 0.0, h.z)).r - texture(voxels, uvw - vec3(0.0, 0.0, h.z)).r;
+    return vec3(x, y, z) / (2.0 * h * size);
+}
+#endif
+
+void main() {
+    // Only the back faces of the cube are rendered, so the ray leaves the volume at this fragment
+    // and enters it where it hits the front of the cube or at the camera if the camera is inside the volume
+    vec3 ray_direction = normalize(pos - cameraPosition);
+    vec3 t0 = (-0.5 * size - cameraPosition) / ray_direction;
+    vec3 t1 = (0.5 * size - cameraPosition) / ray_direction;
+    vec3 t_min = min(t0, t1);
+    float t_enter = max(max(max(t_min.x, t_min.y), t_min.z), 0.0);
+    float t_exit = distance(cameraPosition, pos);
+    float step_size = length(size) / float(stepCount);
+
+#ifdef USE_MAXIMUM_INTENSITY_PROJECTION
+    float max_value = 0.0;
+    for (int i = 0; i < stepCount; i++) {
+        float t = t_enter + (float(i) + 0.5) * step_size;
+        if (t > t_exit) {
+            break;
+        }
+        vec3 uvw = (cameraPosition + t * ray_direction) / size + 0.5;
+        max_value = max(max_value, texture(voxels, uvw).r);
+    }
+    outColor = transfer_function(max_value);
+#else
+    // The alpha value of the transfer function is the opacity of one voxel, so it is corrected for the step size
+    vec3 voxel_size = h * size;
+    float opacity_exponent = opacityScale * step_size / min(min(voxel_size.x, voxel_size.y), voxel_size.z);
+
+    // Front to back compositing with premultiplied alpha
+    vec4 color = vec4(0.0);
+    for (int i = 0; i < stepCount; i++) {
+        float t = t_enter + (float(i) + 0.5) * step_size;
+        if (t > t_exit) {
+            break;
+        }
+        vec3 position = cameraPosition + t * ray_direction;
+        vec3 uvw = position / size + 0.5;
+        vec4 sample_color = transfer_function(texture(voxels, uvw).r);
+        float alpha = 1.0 - pow(1.0 - sample_color.a, opacity_exponent);
+        if (alpha > 0.0) {
+#ifdef USE_SHADING
+            // The gradient points towards higher values, so the normal points out of the denser parts of the volume
+            vec3 gradient = estimate_gradient(uvw);
+            if (dot(gradient, gradient) > 0.000001) {
+                vec3 normal = -normalize(gradient);
+                normal = dot(normal, ray_direction) > 0.0 ? -normal : normal;
+                sample_color.rgb = calculate_lighting(cameraPosition, sample_color.rgb, position, normal, 0.0, roughness, 1.0);
+            }
+#endif
+            color += (1.0 - color.a) * alpha * vec4(sample_color.rgb, 1.0);
+            // Early ray termination when nothing behind is visible anyway
+            if (color.a > 0.99) {
+                break;
+            }
+        }
+    }
+    outColor = vec4(color.rgb / max(color.a, 0.0001), color.a);
+#endif
+
+#ifdef USE_SHADING
+    outColor.rgb = tone_mapping(outColor.rgb);
+#endif
+    outColor.rgb = color_mapping(outColor.rgb);
+}
@@ -0,0 +1,16 @@
+
+uniform vec4 wireframeColor;
+uniform float wireframeWidth;
+
+in vec3 barycentric;
+
+// Draws the edges of the triangle on top of the given color, where the distance to the edges in pixels is found using the screen space derivatives
+// of the barycentric coordinates and the edges are smoothed over one pixel to avoid aliasing.
+// The edges are shared by two triangles, so each triangle draws half of the width.
+vec4 add_wireframe(vec4 color)
+{
+    vec3 distances = barycentric / max(fwidth(barycentric), vec3(0.00001));
+    float distance = min(min(distances.x, distances.y), distances.z);
+    float coverage = wireframeColor.a * (1.0 - clamp(distance - 0.5 * wireframeWidth + 0.5, 0.0, 1.0));
+    return vec4(mix(color.rgb, wireframeColor.rgb, coverage), mix(color.a, 1.0, coverage));
+}
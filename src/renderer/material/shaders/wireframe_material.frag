@@ -0,0 +1,24 @@
+
+uniform vec3 cameraPosition;
+uniform vec4 surfaceColor;
+uniform float metallic;
+uniform float roughness;
+
+in vec3 pos;
+in vec4 col;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    // The normal of the triangle, which gives a faceted look that matches the wireframe and does not require normals
+    vec3 normal = normalize(cross(dFdx(pos), dFdy(pos)));
+    normal = dot(normal, cameraPosition - pos) < 0.0 ? -normal : normal;
+
+    vec4 surface_color = surfaceColor * col;
+    outColor.rgb = calculate_lighting(cameraPosition, surface_color.rgb, pos, normal, metallic, roughness, 1.0);
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.a = surface_color.a;
+    outColor = add_wireframe(outColor);
+    outColor.rgb = color_mapping(outColor.rgb);
+}
@@ -0,0 +1,218 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+/// The maximum number of layers in a [TerrainMaterial].
+pub const MAX_TERRAIN_LAYERS: usize = 8;
+
+///
+/// The parameters of one layer of a [TerrainMaterial], for example grass, rock, sand or snow.
+/// Except for the first layer, which covers the entire terrain, a layer is only applied within the given height and slope ranges
+/// and where the weight in the splat maps is larger than zero.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct TerrainLayer {
+    /// A color which is multiplied with the color in the albedo texture of the layer.
+    pub color: Srgba,
+    /// A value in the range `[0..1]` specifying how metallic the layer is.
+    pub metallic: f32,
+    /// A value in the range `[0..1]` specifying how rough the layer is.
+    pub roughness: f32,
+    /// The number of times the textures of the layer are repeated per world unit.
+    pub texture_scale: f32,
+    /// The height below which the layer is not applied.
+    pub min_height: f32,
+    /// The height above which the layer is not applied.
+    pub max_height: f32,
+    /// The angle between the surface and the horizontal plane below which the layer is not applied.
+    pub min_slope: Radians,
+    /// The angle between the surface and the horizontal plane above which the layer is not applied.
+    pub max_slope: Radians,
+}
+
+impl Default for TerrainLayer {
+    fn default() -> Self {
+        Self {
+            color: Srgba::WHITE,
+            metallic: 0.0,
+            roughness: 1.0,
+            texture_scale: 1.0,
+            min_height: f32::MIN,
+            max_height: f32::MAX,
+            min_slope: radians(0.0),
+            max_slope: degrees(90.0).into(),
+        }
+    }
+}
+
+///
+/// A material for a landscape, for example a [Terrain], consisting of up to [MAX_TERRAIN_LAYERS] [TerrainLayer]s.
+/// Each layer is painted on top of the previous layers, where the layer is applied depending on the height and slope of the surface
+/// and optionally on the weights painted in a set of splat maps.
+/// The textures of the layers are mapped using the world position, so the geometry does not need uv coordinates.
+///
+#[derive(Clone)]
+pub struct TerrainMaterial {
+    /// The layers of the terrain. Only the first [MAX_TERRAIN_LAYERS] layers are used.
+    pub layers: Vec<TerrainLayer>,
+    /// The albedo textures of the layers, where the texture of a layer is at the same index in the array as the layer.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub albedo_textures: Arc<Texture2DArray>,
+    /// The tangent space normal maps of the layers, where the normal map of a layer is at the same index in the array as the layer.
+    pub normal_textures: Option<Arc<Texture2DArray>>,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_textures].
+    pub normal_scale: f32,
+    /// Maps with the weight of each layer, where the weight of layer `i` is stored in the channel `i % 4` of the texture at index `i / 4` in the array.
+    /// The weight of a layer is multiplied with the weights given by the height and slope ranges of the layer.
+    pub splat_maps: Option<Arc<Texture2DArray>>,
+    /// The position in the horizontal plane (x and z) of the corner of the splat maps with uv coordinates `(0, 0)`.
+    pub splat_map_origin: Vec2,
+    /// The size in the horizontal plane (x and z) of the area covered by the splat maps.
+    pub splat_map_size: Vec2,
+    /// The distance over which a layer fades in and out at the ends of its height range.
+    pub height_blend: f32,
+    /// The angle over which a layer fades in and out at the ends of its slope range.
+    pub slope_blend: Radians,
+    /// Whether to project the textures along all three world axes, also known as triplanar mapping,
+    /// which avoids that the textures are stretched on cliffs at the cost of three times the number of texture lookups.
+    /// If false, the textures are projected from above.
+    pub triplanar: bool,
+    /// How sharp the transitions between the three projections are when using [triplanar](Self::triplanar) mapping.
+    pub blend_sharpness: f32,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// The lighting model used when rendering this material
+    pub lighting_model: LightingModel,
+}
+
+impl TerrainMaterial {
+    ///
+    /// Constructs a new terrain material with the given layers and albedo textures, one for each layer.
+    /// The albedo textures must have the same size and format.
+    ///
+    pub fn new(
+        context: &Context,
+        layers: Vec<TerrainLayer>,
+        albedo_textures: &[&CpuTexture],
+    ) -> Self {
+        let albedo_textures = albedo_textures
+            .iter()
+            .map(|cpu_texture| {
+                let mut cpu_texture = (*cpu_texture).clone();
+                if let TextureData::RgbU8(_) | TextureData::RgbaU8(_) = cpu_texture.data {
+                    cpu_texture.data.to_linear_srgb();
+                }
+                cpu_texture
+            })
+            .collect::<Vec<_>>();
+        Self {
+            layers,
+            albedo_textures: Arc::new(Texture2DArray::new(
+                context,
+                &albedo_textures.iter().collect::<Vec<_>>(),
+            )),
+            normal_textures: None,
+            normal_scale: 1.0,
+            splat_maps: None,
+            splat_map_origin: vec2(0.0, 0.0),
+            splat_map_size: vec2(1.0, 1.0),
+            height_blend: 1.0,
+            slope_blend: degrees(5.0).into(),
+            triplanar: false,
+            blend_sharpness: 4.0,
+            render_states: RenderStates::default(),
+            lighting_model: LightingModel::Blinn,
+        }
+    }
+}
+
+impl Material for TerrainMaterial {
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::TerrainMaterial(
+            self.normal_textures.is_some(),
+            self.splat_maps.is_some(),
+            self.triplanar,
+        )
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut output = lights_shader_source(lights);
+        output.push_str(&format!(
+            "#define MAX_TERRAIN_LAYERS {MAX_TERRAIN_LAYERS}\n"
+        ));
+        if self.normal_textures.is_some() {
+            output.push_str("#define USE_NORMAL_TEXTURES;\n");
+        }
+        if self.splat_maps.is_some() {
+            output.push_str("#define USE_SPLAT_MAPS;\n");
+        }
+        if self.triplanar {
+            output.push_str("#define USE_TRIPLANAR;\n");
+        }
+        output.push_str(ToneMapping::fragment_shader_source());
+        output.push_str(ColorMapping::fragment_shader_source());
+        output.push_str(include_str!("shaders/terrain_material.frag"));
+        output
+    }
+
+    fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, lights: &[&dyn Light]) {
+        program.use_uniform_if_required("lightingModel", lighting_model_to_id(self.lighting_model));
+        viewer.tone_mapping().use_uniforms(program);
+        viewer.color_mapping().use_uniforms(program);
+        program.use_uniform_if_required("cameraPosition", viewer.position());
+        for (i, light) in lights.iter().enumerate() {
+            light.use_uniforms(program, i as u32);
+        }
+
+        let mut layers = [TerrainLayer::default(); MAX_TERRAIN_LAYERS];
+        let layer_count = self.layers.len().min(MAX_TERRAIN_LAYERS);
+        layers[..layer_count].copy_from_slice(&self.layers[..layer_count]);
+        program.use_uniform("layerCount", layer_count as i32);
+        program.use_uniform_array(
+            "layerColors",
+            &layers
+                .iter()
+                .map(|l| l.color.to_linear_srgb())
+                .collect::<Vec<_>>(),
+        );
+        program.use_uniform_array(
+            "layerMaterials",
+            &layers
+                .iter()
+                .map(|l| vec4(l.metallic, l.roughness, l.texture_scale, 0.0))
+                .collect::<Vec<_>>(),
+        );
+        program.use_uniform_array(
+            "layerRules",
+            &layers
+                .iter()
+                .map(|l| vec4(l.min_height, l.max_height, l.min_slope.0, l.max_slope.0))
+                .collect::<Vec<_>>(),
+        );
+        program.use_uniform("heightBlend", self.height_blend.max(0.0001));
+        program.use_uniform("slopeBlend", self.slope_blend.0.max(0.0001));
+        program.use_texture_array("albedoTextures", &self.albedo_textures);
+        if program.requires_uniform("normalTextures") {
+            if let Some(ref normal_textures) = self.normal_textures {
+                program.use_uniform("normalScale", self.normal_scale);
+                program.use_texture_array("normalTextures", normal_textures);
+            }
+        }
+        if let Some(ref splat_maps) = self.splat_maps {
+            program.use_uniform("splatMapOrigin", self.splat_map_origin);
+            program.use_uniform("splatMapSize", self.splat_map_size);
+            program.use_texture_array("splatMaps", splat_maps);
+        }
+        if self.triplanar {
+            program.use_uniform("blendSharpness", self.blend_sharpness);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
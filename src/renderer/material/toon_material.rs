@@ -0,0 +1,201 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// A material for stylised rendering, also known as cel shading, where the light from each light source is quantised into a few flat shades
+/// and the specular reflections into sharp highlights. Optionally, the edges of the object facing away from the viewer are outlined with a rim light.
+/// The light is calculated using the same lights as for example [PhysicalMaterial], so this material also receives shadows.
+///
+/// The light from a light source is normalised so that a white surface facing a light with an intensity of one is fully lit.
+/// This also applies to the light from an [AmbientLight], which therefore should have a low intensity.
+///
+#[derive(Clone)]
+pub struct ToonMaterial {
+    /// Albedo base color, also called diffuse color.
+    pub albedo: Srgba,
+    /// Texture with albedo base colors, also called diffuse color.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub albedo_texture: Option<Texture2DRef>,
+    /// The number of shades, including unlit, that the light from each light source is rounded to. Must be at least two.
+    /// Not used if a [Self::ramp] is specified.
+    pub bands: u32,
+    /// A ramp which maps the intensity of the light from each light source to a shade, which allows for colored shades and uneven bands.
+    /// The ramp is read from left (unlit) to right (fully lit) along the middle row of the texture, so a texture with a height of one is enough.
+    pub ramp: Option<Arc<Texture2D>>,
+    /// The strength of the specular highlights. Set to zero to disable the highlights.
+    pub specular: f32,
+    /// A value in the range `[0..1]` specifying how rough the surface is, where a rougher surface has larger specular highlights.
+    pub roughness: f32,
+    /// The color of the rim light. Set to black to disable the rim light.
+    pub rim_color: Srgba,
+    /// A value in the range `[0..1]` specifying the width of the rim light, where zero is no rim and one covers the entire surface.
+    pub rim_width: f32,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+}
+
+impl ToonMaterial {
+    ///
+    /// Constructs a new toon material from a [CpuMaterial] where only the albedo color and texture are used.
+    /// Tries to infer whether this material is transparent or opaque from the alpha value of the albedo color and the alpha values in the albedo texture.
+    ///
+    pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        let albedo_texture =
+            cpu_material
+                .albedo_texture
+                .as_ref()
+                .map(|cpu_texture| match &cpu_texture.data {
+                    TextureData::RgbU8(_) | TextureData::RgbaU8(_) => {
+                        let mut cpu_texture = cpu_texture.clone();
+                        cpu_texture.data.to_linear_srgb();
+                        Texture2DRef::from_cpu_texture(context, &cpu_texture)
+                    }
+                    _ => Texture2DRef::from_cpu_texture(context, cpu_texture),
+                });
+        let is_transparent = super::is_transparent(cpu_material);
+        Self {
+            albedo: cpu_material.albedo,
+            albedo_texture,
+            render_states: if is_transparent {
+                RenderStates {
+                    write_mask: WriteMask::COLOR,
+                    blend: Blend::TRANSPARENCY,
+                    ..Default::default()
+                }
+            } else {
+                RenderStates::default()
+            },
+            is_transparent,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a toon material from a [PhysicalMaterial], where the specular highlights have the roughness of the physical material.
+    pub fn from_physical_material(physical_material: &PhysicalMaterial) -> Self {
+        Self {
+            albedo: physical_material.albedo,
+            albedo_texture: physical_material.albedo_texture.clone(),
+            roughness: physical_material.roughness,
+            render_states: physical_material.render_states,
+            is_transparent: physical_material.is_transparent,
+            ..Default::default()
+        }
+    }
+}
+
+impl FromCpuMaterial for ToonMaterial {
+    fn from_cpu_material(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        Self::new(context, cpu_material)
+    }
+}
+
+impl Material for ToonMaterial {
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::ToonMaterial(self.albedo_texture.is_some(), self.ramp.is_some())
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut output = lights_shader_source(lights);
+        if self.albedo_texture.is_some() {
+            output.push_str("in vec2 uvs;\n#define USE_ALBEDO_TEXTURE;\n");
+        }
+        if self.ramp.is_some() {
+            output.push_str("#define USE_RAMP;\n");
+        }
+        output.push_str(ToneMapping::fragment_shader_source());
+        output.push_str(ColorMapping::fragment_shader_source());
+        output.push_str(include_str!("shaders/toon_material.frag"));
+
+        // Each light is evaluated twice for a white surface, once without specular reflection and once without diffuse reflection,
+        // so that the diffuse and specular light can be quantised separately
+        let mut toon_lighting = String::new();
+        for i in 0..lights.len() {
+            toon_lighting.push_str(&format!(
+                "
+                    diffuse += toon_diffuse(calculate_lighting{i}(vec3(1.0), position, normal, view_direction, 0.0, 1.0, 1.0));
+                    specular += toon_specular(calculate_lighting{i}(vec3(1.0), position, normal, view_direction, 1.0, roughness, 1.0));
+                "
+            ));
+        }
+        output.push_str(&format!(
+            "
+                void calculate_toon_lighting(vec3 position, vec3 normal, vec3 view_direction, out vec3 diffuse, out vec3 specular)
+                {{
+                    diffuse = vec3(0.0);
+                    specular = vec3(0.0);
+                    {toon_lighting}
+                }}
+            "
+        ));
+        output
+    }
+
+    fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, lights: &[&dyn Light]) {
+        program.use_uniform_if_required(
+            "lightingModel",
+            lighting_model_to_id(LightingModel::Cook(
+                NormalDistributionFunction::TrowbridgeReitzGGX,
+                GeometryFunction::SmithSchlickGGX,
+            )),
+        );
+        viewer.tone_mapping().use_uniforms(program);
+        viewer.color_mapping().use_uniforms(program);
+        program.use_uniform("cameraPosition", viewer.position());
+        for (i, light) in lights.iter().enumerate() {
+            light.use_uniforms(program, i as u32);
+        }
+        program.use_uniform("albedo", self.albedo.to_linear_srgb());
+        if let Some(ref texture) = self.albedo_texture {
+            program.use_uniform("albedoTexTransform", texture.transformation);
+            program.use_texture("albedoTexture", texture);
+        }
+        if let Some(ref ramp) = self.ramp {
+            program.use_texture("ramp", ramp);
+        } else {
+            program.use_uniform("bands", self.bands.max(2) as f32);
+        }
+        program.use_uniform_if_required("specularStrength", self.specular);
+        program.use_uniform_if_required("roughness", self.roughness);
+        program.use_uniform_if_required("rimColor", self.rim_color.to_linear_srgb().truncate());
+        program.use_uniform_if_required("rimWidth", self.rim_width);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        Some(TransmittanceMaterial::new(
+            self.albedo,
+            self.albedo_texture.clone(),
+        ))
+    }
+}
+
+impl Default for ToonMaterial {
+    fn default() -> Self {
+        Self {
+            albedo: Srgba::WHITE,
+            albedo_texture: None,
+            bands: 3,
+            ramp: None,
+            specular: 1.0,
+            roughness: 0.3,
+            rim_color: Srgba::BLACK,
+            rim_width: 0.3,
+            render_states: RenderStates::default(),
+            is_transparent: false,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Used for rendering the transmittance of a transparent object, ie. the fraction of the light of each color channel which passes through the object.
+/// A surface with the color `c` and alpha value `a` lets `1 - a + a * c` of the light through, so a fully transparent surface lets all light through
+/// and a fully opaque surface filters the light through its color.
+/// This is used by lights to cast colored shadows from transparent objects, see for example [DirectionalLight::generate_shadow_map_with_transmittance],
+/// and is returned from [Material::transmittance_material].
+///
+#[derive(Clone)]
+pub struct TransmittanceMaterial {
+    /// Base surface color including the alpha value.
+    pub color: Srgba,
+    /// An optional texture which is multiplied with the color (requires that the [Geometry] supports uv coordinates).
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub texture: Option<Texture2DRef>,
+    /// Fragments with an alpha value below this value are discarded, so they do not cast any shadow.
+    /// Fully transparent fragments are always discarded.
+    pub alpha_cutout: Option<f32>,
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl TransmittanceMaterial {
+    ///
+    /// Constructs a new transmittance material with the given color and texture and render states that multiply the transmittance into the render target.
+    ///
+    pub fn new(color: Srgba, texture: Option<Texture2DRef>) -> Self {
+        Self {
+            color,
+            texture,
+            alpha_cutout: None,
+            render_states: RenderStates {
+                write_mask: WriteMask::COLOR,
+                depth_test: DepthTest::Always,
+                blend: Blend::Enabled {
+                    source_rgb_multiplier: BlendMultiplierType::Zero,
+                    source_alpha_multiplier: BlendMultiplierType::Zero,
+                    destination_rgb_multiplier: BlendMultiplierType::SrcColor,
+                    destination_alpha_multiplier: BlendMultiplierType::One,
+                    rgb_equation: BlendEquationType::Add,
+                    alpha_equation: BlendEquationType::Add,
+                },
+                cull: Cull::None,
+            },
+        }
+    }
+}
+
+impl Material for TransmittanceMaterial {
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::TransmittanceMaterial(self.texture.is_some(), self.alpha_cutout.is_some())
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        let mut shader = String::new();
+        if self.texture.is_some() {
+            shader.push_str("#define USE_TEXTURE\nin vec2 uvs;\n");
+        }
+        if self.alpha_cutout.is_some() {
+            shader.push_str("#define ALPHACUT\n");
+        }
+        shader.push_str(include_str!("shaders/transmittance_material.frag"));
+        shader
+    }
+
+    fn use_uniforms(&self, program: &Program, _viewer: &dyn Viewer, _lights: &[&dyn Light]) {
+        program.use_uniform("surfaceColor", self.color.to_linear_srgb());
+        if let Some(ref tex) = self.texture {
+            program.use_uniform("textureTransformation", tex.transformation);
+            program.use_texture("tex", tex);
+        }
+        if let Some(alpha_cutout) = self.alpha_cutout {
+            program.use_uniform("alphaCutout", alpha_cutout);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Transparent
+    }
+}
@@ -0,0 +1,187 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Similar to [PhysicalMaterial] except that the textures are projected onto the surface along the world x, y and z axes instead of using uv coordinates,
+/// also known as triplanar mapping. The three projections are blended depending on the direction of the surface normal.
+/// This is useful for geometries which do not have usable uv coordinates, for example a [Terrain] or a procedurally generated mesh,
+/// where textures mapped using uv coordinates are stretched on steep slopes.
+/// Only the positions and normals of the [Geometry] are used.
+///
+#[derive(Clone)]
+pub struct TriplanarPhysicalMaterial {
+    /// Name.
+    pub name: String,
+    /// Albedo base color, also called diffuse color.
+    pub albedo: Srgba,
+    /// Texture with albedo base colors, also called diffuse color.
+    /// The colors are assumed to be in linear sRGB (`RgbU8`), linear sRGB with an alpha channel (`RgbaU8`) or HDR color space.
+    pub albedo_texture: Option<Texture2DRef>,
+    /// A value in the range `[0..1]` specifying how metallic the surface is.
+    pub metallic: f32,
+    /// A value in the range `[0..1]` specifying how rough the surface is.
+    pub roughness: f32,
+    /// Texture containing the metallic and roughness parameters which are multiplied with the [Self::metallic] and [Self::roughness] values in the shader.
+    /// The metallic values are sampled from the blue channel and the roughness from the green channel.
+    pub metallic_roughness_texture: Option<Texture2DRef>,
+    /// A scalar multiplier controlling the amount of occlusion applied from the [Self::occlusion_texture]. A value of 0.0 means no occlusion. A value of 1.0 means full occlusion.
+    pub occlusion_strength: f32,
+    /// An occlusion map. Higher values indicate areas that should receive full indirect lighting and lower values indicate no indirect lighting.
+    /// The occlusion values are sampled from the red channel.
+    pub occlusion_texture: Option<Texture2DRef>,
+    /// A scalar multiplier applied to each normal vector of the [Self::normal_texture].
+    pub normal_scale: f32,
+    /// A tangent space normal map, also known as bump map.
+    /// The tangent frame of each projection is aligned with the world axes, so no tangents are needed.
+    pub normal_texture: Option<Texture2DRef>,
+    /// Color of light shining from an object.
+    pub emissive: Srgba,
+    /// The number of times the textures are repeated per world unit along each of the world axes.
+    pub texture_scale: Vec3,
+    /// How sharp the transitions between the three projections are, where a higher value gives narrower transitions.
+    /// A value of one blends the projections linearly with the components of the normal.
+    pub blend_sharpness: f32,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+    /// The lighting model used when rendering this material
+    pub lighting_model: LightingModel,
+}
+
+impl TriplanarPhysicalMaterial {
+    ///
+    /// Constructs a new triplanar physical material from a [CpuMaterial] in the same way as [PhysicalMaterial::new].
+    /// The emissive texture of the [CpuMaterial] is not used.
+    ///
+    pub fn new(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        Self::from_physical_material(&PhysicalMaterial::new(context, cpu_material))
+    }
+
+    /// Constructs a triplanar physical material from a [PhysicalMaterial].
+    pub fn from_physical_material(physical_material: &PhysicalMaterial) -> Self {
+        Self {
+            name: physical_material.name.clone(),
+            albedo: physical_material.albedo,
+            albedo_texture: physical_material.albedo_texture.clone(),
+            metallic: physical_material.metallic,
+            roughness: physical_material.roughness,
+            metallic_roughness_texture: physical_material.metallic_roughness_texture.clone(),
+            occlusion_strength: physical_material.occlusion_strength,
+            occlusion_texture: physical_material.occlusion_texture.clone(),
+            normal_scale: physical_material.normal_scale,
+            normal_texture: physical_material.normal_texture.clone(),
+            emissive: physical_material.emissive,
+            render_states: physical_material.render_states,
+            is_transparent: physical_material.is_transparent,
+            lighting_model: physical_material.lighting_model,
+            ..Default::default()
+        }
+    }
+}
+
+impl FromCpuMaterial for TriplanarPhysicalMaterial {
+    fn from_cpu_material(context: &Context, cpu_material: &CpuMaterial) -> Self {
+        Self::new(context, cpu_material)
+    }
+}
+
+impl Material for TriplanarPhysicalMaterial {
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::TriplanarPhysicalMaterial(
+            self.albedo_texture.is_some(),
+            self.metallic_roughness_texture.is_some(),
+            self.occlusion_texture.is_some(),
+            self.normal_texture.is_some(),
+        )
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut output = lights_shader_source(lights);
+        if self.albedo_texture.is_some() {
+            output.push_str("#define USE_ALBEDO_TEXTURE;\n");
+        }
+        if self.metallic_roughness_texture.is_some() {
+            output.push_str("#define USE_METALLIC_ROUGHNESS_TEXTURE;\n");
+        }
+        if self.occlusion_texture.is_some() {
+            output.push_str("#define USE_OCCLUSION_TEXTURE;\n");
+        }
+        if self.normal_texture.is_some() {
+            output.push_str("#define USE_NORMAL_TEXTURE;\n");
+        }
+        output.push_str(ToneMapping::fragment_shader_source());
+        output.push_str(ColorMapping::fragment_shader_source());
+        output.push_str(include_str!("shaders/triplanar_physical_material.frag"));
+        output
+    }
+
+    fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, lights: &[&dyn Light]) {
+        program.use_uniform_if_required("lightingModel", lighting_model_to_id(self.lighting_model));
+        viewer.tone_mapping().use_uniforms(program);
+        viewer.color_mapping().use_uniforms(program);
+        program.use_uniform_if_required("cameraPosition", viewer.position());
+        for (i, light) in lights.iter().enumerate() {
+            light.use_uniforms(program, i as u32);
+        }
+        program.use_uniform_if_required("metallic", self.metallic);
+        program.use_uniform_if_required("roughness", self.roughness);
+        program.use_uniform("albedo", self.albedo.to_linear_srgb());
+        program.use_uniform("emissive", self.emissive.to_linear_srgb());
+        program.use_uniform_if_required("textureScale", self.texture_scale);
+        program.use_uniform_if_required("blendSharpness", self.blend_sharpness);
+        if let Some(ref texture) = self.albedo_texture {
+            program.use_uniform("albedoTexTransform", texture.transformation);
+            program.use_texture("albedoTexture", texture);
+        }
+        if let Some(ref texture) = self.metallic_roughness_texture {
+            program.use_uniform("metallicRoughnessTexTransform", texture.transformation);
+            program.use_texture("metallicRoughnessTexture", texture);
+        }
+        if let Some(ref texture) = self.occlusion_texture {
+            program.use_uniform("occlusionTexTransform", texture.transformation);
+            program.use_uniform("occlusionStrength", self.occlusion_strength);
+            program.use_texture("occlusionTexture", texture);
+        }
+        if let Some(ref texture) = self.normal_texture {
+            program.use_uniform("normalTexTransform", texture.transformation);
+            program.use_uniform("normalScale", self.normal_scale);
+            program.use_texture("normalTexture", texture);
+        }
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+}
+
+impl Default for TriplanarPhysicalMaterial {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            albedo: Srgba::WHITE,
+            albedo_texture: None,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            occlusion_strength: 1.0,
+            occlusion_texture: None,
+            normal_scale: 1.0,
+            normal_texture: None,
+            emissive: Srgba::BLACK,
+            texture_scale: vec3(1.0, 1.0, 1.0),
+            blend_sharpness: 4.0,
+            render_states: RenderStates::default(),
+            is_transparent: false,
+            lighting_model: LightingModel::Blinn,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::core::*;
+use crate::renderer::*;
+use std::sync::Arc;
+
+///
+/// Specifies how the samples along a ray through the volume are combined in a [VolumeMaterial].
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VolumeRenderingMode {
+    /// The colors and opacities given by the transfer function are accumulated from front to back, so the inside of semi-transparent parts is visible.
+    #[default]
+    Composite,
+    /// Only the maximum value along the ray is mapped through the transfer function, also known as maximum intensity projection,
+    /// which is for example used to show blood vessels in angiography data.
+    MaximumIntensityProjection,
+}
+
+///
+/// A material that renders the voxel data in the [VolumeMaterial::voxels] directly, also known as direct volume rendering,
+/// by marching along a ray through the volume and mapping the value in the red channel of each sample to a color and an opacity using a [transfer function](VolumeMaterial::transfer_function).
+/// As opposed to the [IsosurfaceMaterial], which only shows the surface at one threshold, this shows the entire volume, for example the different tissues in medical data.
+/// Like the [IsosurfaceMaterial], this material should be applied to a cube with center in origo, for example [CpuMesh::cube].
+///
+#[derive(Clone)]
+pub struct VolumeMaterial {
+    /// The voxel data that is rendered.
+    pub voxels: Arc<Texture3D>,
+    /// A one-dimensional texture, ie. a texture with a height of one, which maps a value in the voxel data, from zero at the left to one at the right, to a color and an opacity.
+    /// The alpha value is the opacity of a slab with the thickness of one voxel. See [VolumeMaterial::transfer_function_from_colors].
+    pub transfer_function: Arc<Texture2D>,
+    /// The size of the cube that is used to render the voxel data. The texture is scaled to fill the entire cube.
+    pub size: Vec3,
+    /// How the samples along a ray are combined.
+    pub mode: VolumeRenderingMode,
+    /// A multiplier applied to the opacity of each voxel, where a higher value makes the volume more opaque.
+    /// Only used by the [VolumeRenderingMode::Composite] mode.
+    pub opacity_scale: f32,
+    /// The number of samples along a ray through the diagonal of the cube. More samples give a more accurate result but are slower to render.
+    pub step_count: u32,
+    /// Whether the samples are lit by the scene lights using the gradient of the voxel data as the normal.
+    /// Only used by the [VolumeRenderingMode::Composite] mode.
+    pub shading: bool,
+    /// A value in the range `[0..1]` specifying how rough the volume is when [Self::shading] is enabled.
+    pub roughness: f32,
+    /// The lighting model used when [Self::shading] is enabled.
+    pub lighting_model: LightingModel,
+}
+
+impl VolumeMaterial {
+    ///
+    /// Constructs a new volume material from the given [CpuVoxelGrid] and a transfer function with colors evenly spread from the value zero to one,
+    /// see [VolumeMaterial::transfer_function_from_colors].
+    ///
+    pub fn new(
+        context: &Context,
+        cpu_voxel_grid: &CpuVoxelGrid,
+        transfer_function: &[Srgba],
+    ) -> Self {
+        Self {
+            voxels: Arc::new(Texture3D::new(context, &cpu_voxel_grid.voxels)),
+            transfer_function: Self::transfer_function_from_colors(context, transfer_function),
+            size: cpu_voxel_grid.size,
+            mode: VolumeRenderingMode::Composite,
+            opacity_scale: 1.0,
+            step_count: 256,
+            shading: false,
+            roughness: 1.0,
+            lighting_model: LightingModel::Blinn,
+        }
+    }
+
+    ///
+    /// Creates a transfer function texture from the given colors, where the first color is used for the value zero, the last color for the value one
+    /// and the colors in between are evenly spread and linearly interpolated.
+    ///
+    pub fn transfer_function_from_colors(context: &Context, colors: &[Srgba]) -> Arc<Texture2D> {
+        let mut data = TextureData::RgbaU8(colors.iter().map(|c| (*c).into()).collect());
+        data.to_linear_srgb();
+        Arc::new(Texture2D::new(
+            context,
+            &CpuTexture {
+                name: "transfer function".to_owned(),
+                data,
+                width: colors.len() as u32,
+                height: 1,
+                mipmap: None,
+                wrap_s: Wrapping::ClampToEdge,
+                wrap_t: Wrapping::ClampToEdge,
+                ..Default::default()
+            },
+        ))
+    }
+}
+
+impl Material for VolumeMaterial {
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::VolumeMaterial(
+            self.shading && self.mode == VolumeRenderingMode::Composite,
+            self.mode == VolumeRenderingMode::MaximumIntensityProjection,
+        )
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut source = String::new();
+        match self.mode {
+            VolumeRenderingMode::Composite => {
+                if self.shading {
+                    source.push_str(&lights_shader_source(lights));
+                    source.push_str("#define USE_SHADING\n");
+                    source.push_str(ToneMapping::fragment_shader_source());
+                }
+            }
+            VolumeRenderingMode::MaximumIntensityProjection => {
+                source.push_str("#define USE_MAXIMUM_INTENSITY_PROJECTION\n");
+            }
+        }
+        source.push_str(ColorMapping::fragment_shader_source());
+        source.push_str(include_str!("shaders/volume_material.frag"));
+        source
+    }
+
+    fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, lights: &[&dyn Light]) {
+        viewer.color_mapping().use_uniforms(program);
+        if program.requires_uniform("roughness") {
+            program.use_uniform_if_required(
+                "lightingModel",
+                lighting_model_to_id(self.lighting_model),
+            );
+            viewer.tone_mapping().use_uniforms(program);
+            for (i, light) in lights.iter().enumerate() {
+                light.use_uniforms(program, i as u32);
+            }
+            program.use_uniform("roughness", self.roughness);
+        }
+        program.use_uniform("cameraPosition", viewer.position());
+        program.use_uniform("size", self.size);
+        program.use_uniform("stepCount", self.step_count.max(1) as i32);
+        program.use_uniform_if_required("opacityScale", self.opacity_scale);
+        program.use_uniform_if_required(
+            "h",
+            vec3(
+                1.0 / self.voxels.width() as f32,
+                1.0 / self.voxels.height() as f32,
+                1.0 / self.voxels.depth() as f32,
+            ),
+        );
+        program.use_texture_3d("voxels", &self.voxels);
+        program.use_texture("transferFunction", &self.transfer_function);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        // The rays are cast from the back faces, so each pixel is only rendered once, also when the camera is inside the volume
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            blend: Blend::TRANSPARENCY,
+            cull: Cull::Front,
+            ..Default::default()
+        }
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Transparent
+    }
+}
+
+impl FromCpuVoxelGrid for VolumeMaterial {
+    fn from_cpu_voxel_grid(context: &Context, cpu_voxel_grid: &CpuVoxelGrid) -> Self {
+        Self::new(
+            context,
+            cpu_voxel_grid,
+            &[Srgba::new(0, 0, 0, 0), Srgba::new(255, 255, 255, 64)],
+        )
+    }
+}
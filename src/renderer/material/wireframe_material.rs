@@ -0,0 +1,152 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// The edges of the triangles drawn on top of a surface by a [WireframeMaterial] or a [PhysicalMaterial] with a [wireframe](PhysicalMaterial::wireframe).
+///
+/// The edges are found using the barycentric coordinates of the triangles, which are provided by a [Mesh], [InstancedMesh] and [ParticleSystem]
+/// and which other geometries must provide as described in [Geometry].
+/// If the triangles of a mesh share vertices, ie. if the indices of the [CpuMesh] is not [Indices::None], the vertex data is read from the GPU
+/// the first time the mesh is rendered with a wireframe and a copy of the mesh where each triangle has its own three vertices is used instead.
+/// The copy is created again if the mesh is edited, for example using [Mesh::positions_mut].
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Wireframe {
+    /// The color of the edges, where the alpha value specifies how much the edges cover the surface.
+    pub color: Srgba,
+    /// The width of the edges in pixels.
+    pub width: f32,
+}
+
+impl Wireframe {
+    pub(in crate::renderer) fn fragment_shader_source() -> &'static str {
+        include_str!("shaders/wireframe.frag")
+    }
+
+    pub(in crate::renderer) fn use_uniforms(&self, program: &Program) {
+        program.use_uniform("wireframeColor", self.color.to_linear_srgb());
+        program.use_uniform("wireframeWidth", self.width);
+    }
+}
+
+impl Default for Wireframe {
+    fn default() -> Self {
+        Self {
+            color: Srgba::BLACK,
+            width: 1.0,
+        }
+    }
+}
+
+///
+/// A material that draws anti-aliased edges of the triangles on top of the surface in a single render pass, which is useful for inspecting the triangulation of a mesh.
+/// The surface is lit by the lights using the normal of each triangle, so the geometry does not need normals.
+/// To only show the edges, set the alpha value of the [Self::color] to zero.
+///
+/// See [Wireframe] for the requirements to the geometry.
+///
+#[derive(Clone)]
+pub struct WireframeMaterial {
+    /// Base surface color.
+    pub color: Srgba,
+    /// A value in the range `[0..1]` specifying how metallic the surface is.
+    pub metallic: f32,
+    /// A value in the range `[0..1]` specifying how rough the surface is.
+    pub roughness: f32,
+    /// The edges of the triangles.
+    pub wireframe: Wireframe,
+    /// Render states.
+    pub render_states: RenderStates,
+    /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
+    pub is_transparent: bool,
+    /// The lighting model used when rendering this material
+    pub lighting_model: LightingModel,
+}
+
+impl WireframeMaterial {
+    ///
+    /// Constructs a new wireframe material from a [CpuMaterial] where only the albedo color, metallic and roughness parameters are used.
+    /// If the albedo color is transparent, the material is transparent.
+    ///
+    pub fn new(cpu_material: &CpuMaterial) -> Self {
+        let is_transparent = cpu_material.albedo.a != 255;
+        Self {
+            color: cpu_material.albedo,
+            metallic: cpu_material.metallic,
+            roughness: cpu_material.roughness,
+            render_states: if is_transparent {
+                RenderStates {
+                    write_mask: WriteMask::COLOR,
+                    blend: Blend::TRANSPARENCY,
+                    ..Default::default()
+                }
+            } else {
+                RenderStates::default()
+            },
+            is_transparent,
+            lighting_model: cpu_material.lighting_model,
+            ..Default::default()
+        }
+    }
+}
+
+impl FromCpuMaterial for WireframeMaterial {
+    fn from_cpu_material(_context: &Context, cpu_material: &CpuMaterial) -> Self {
+        Self::new(cpu_material)
+    }
+}
+
+impl Material for WireframeMaterial {
+    fn id(&self) -> EffectMaterialId {
+        EffectMaterialId::WireframeMaterial
+    }
+
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        let mut output = lights_shader_source(lights);
+        output.push_str(ToneMapping::fragment_shader_source());
+        output.push_str(ColorMapping::fragment_shader_source());
+        output.push_str(Wireframe::fragment_shader_source());
+        output.push_str(include_str!("shaders/wireframe_material.frag"));
+        output
+    }
+
+    fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, lights: &[&dyn Light]) {
+        program.use_uniform_if_required("lightingModel", lighting_model_to_id(self.lighting_model));
+        viewer.tone_mapping().use_uniforms(program);
+        viewer.color_mapping().use_uniforms(program);
+        program.use_uniform_if_required("cameraPosition", viewer.position());
+        for (i, light) in lights.iter().enumerate() {
+            light.use_uniforms(program, i as u32);
+        }
+        program.use_uniform("surfaceColor", self.color.to_linear_srgb());
+        program.use_uniform_if_required("metallic", self.metallic);
+        program.use_uniform_if_required("roughness", self.roughness);
+        self.wireframe.use_uniforms(program);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        if self.is_transparent {
+            MaterialType::Transparent
+        } else {
+            MaterialType::Opaque
+        }
+    }
+}
+
+impl Default for WireframeMaterial {
+    fn default() -> Self {
+        Self {
+            color: Srgba::WHITE,
+            metallic: 0.0,
+            roughness: 1.0,
+            wireframe: Wireframe::default(),
+            render_states: RenderStates::default(),
+            is_transparent: false,
+            lighting_model: LightingModel::Blinn,
+        }
+    }
+}
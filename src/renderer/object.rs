@@ -15,6 +15,28 @@ macro_rules! impl_object_body {
         fn material_type(&self) -> MaterialType {
             self.$inner().material_type()
         }
+
+        fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+            self.$inner().transmittance_material()
+        }
+
+        fn depth_material(&self) -> Option<DepthMaterial> {
+            self.$inner().depth_material()
+        }
+
+        fn uses_extensions_layer(&self) -> bool {
+            self.$inner().uses_extensions_layer()
+        }
+
+        fn render_order_independent(
+            &self,
+            context: &Context,
+            viewer: &dyn Viewer,
+            lights: &[&dyn Light],
+        ) -> bool {
+            self.$inner()
+                .render_order_independent(context, viewer, lights)
+        }
     };
 }
 
@@ -72,6 +94,41 @@ pub trait Object: Geometry {
     /// Returns the type of material applied to this object.
     ///
     fn material_type(&self) -> MaterialType;
+
+    ///
+    /// Returns the material used to render the transmittance of this object when it casts colored shadows, see [Material::transmittance_material].
+    ///
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        None
+    }
+
+    ///
+    /// Returns the material used instead of a default [DepthMaterial] when rendering the depth of this object, for example into a shadow map, see [Material::depth_material].
+    ///
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        None
+    }
+
+    ///
+    /// Returns whether the material of this object writes to the optional fourth layer of the geometry pass, see [Material::uses_extensions_layer].
+    ///
+    fn uses_extensions_layer(&self) -> bool {
+        false
+    }
+
+    ///
+    /// Render the transparent object into the accumulation and revealage targets used by [RenderTarget::render_with_order_independent_transparency], usually by calling [render_order_independent].
+    /// Returns whether the object supports order-independent transparency.
+    /// The default implementation does not render anything and returns false, in which case the object is rendered in the order given by [cmp_render_order] instead.
+    ///
+    fn render_order_independent(
+        &self,
+        _context: &Context,
+        _viewer: &dyn Viewer,
+        _lights: &[&dyn Light],
+    ) -> bool {
+        false
+    }
 }
 
 use std::ops::Deref;
@@ -107,4 +164,27 @@ impl<T: Object> Object for std::sync::RwLock<T> {
     fn material_type(&self) -> MaterialType {
         self.read().unwrap().material_type()
     }
+
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        self.read().unwrap().transmittance_material()
+    }
+
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        self.read().unwrap().depth_material()
+    }
+
+    fn uses_extensions_layer(&self) -> bool {
+        self.read().unwrap().uses_extensions_layer()
+    }
+
+    fn render_order_independent(
+        &self,
+        context: &Context,
+        viewer: &dyn Viewer,
+        lights: &[&dyn Light],
+    ) -> bool {
+        self.read()
+            .unwrap()
+            .render_order_independent(context, viewer, lights)
+    }
 }
@@ -1,4 +1,5 @@
 use crate::renderer::*;
+use std::ops::Deref;
 
 ///
 /// A combination of a [Geometry] and a [Material] which implements [Object].
@@ -39,7 +40,6 @@ impl<G: Geometry + Clone, M: Material + Clone> Clone for Gm<G, M> {
     }
 }
 
-use std::ops::Deref;
 impl<G: Geometry, M: Material> std::ops::Deref for Gm<G, M> {
     type Target = G;
     fn deref(&self) -> &Self::Target {
@@ -69,4 +69,26 @@ impl<G: Geometry, M: Material> Object for Gm<G, M> {
     fn material_type(&self) -> MaterialType {
         self.material.material_type()
     }
+
+    fn transmittance_material(&self) -> Option<TransmittanceMaterial> {
+        self.material.transmittance_material()
+    }
+
+    fn depth_material(&self) -> Option<DepthMaterial> {
+        self.material.depth_material()
+    }
+
+    fn uses_extensions_layer(&self) -> bool {
+        self.material.uses_extensions_layer()
+    }
+
+    fn render_order_independent(
+        &self,
+        context: &Context,
+        viewer: &dyn Viewer,
+        lights: &[&dyn Light],
+    ) -> bool {
+        render_order_independent(context, viewer, &self.geometry, &self.material, lights);
+        true
+    }
 }
@@ -117,6 +117,16 @@ impl Object for Imposters {
     fn material_type(&self) -> MaterialType {
         self.material.material_type()
     }
+
+    fn render_order_independent(
+        &self,
+        context: &Context,
+        viewer: &dyn Viewer,
+        lights: &[&dyn Light],
+    ) -> bool {
+        render_order_independent(context, viewer, &self, &self.material, lights);
+        true
+    }
 }
 
 struct ImpostersMaterial {
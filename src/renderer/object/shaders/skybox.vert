@@ -5,9 +5,12 @@ uniform mat4 projection;
 in vec3 position;
 
 out vec3 coords;
+out vec3 pos;
 
 void main()
 {
     coords = position;
+    // The skybox is infinitely far away, so the position is only provided for materials that require it, for example the DepthMaterial
+    pos = position;
     gl_Position = (projection * mat4(mat3(view)) * vec4(position, 1.)).xyww;
 }
\ No newline at end of file
@@ -16,6 +16,7 @@ const VERTICES_PER_SIDE: usize = 33;
 
 ///
 /// A terrain geometry based on a height map and with an applied material.
+/// Use a [TerrainMaterial] to blend several layers, for example grass, rock and snow, depending on the height and slope of the terrain.
 ///
 pub struct Terrain<M: Material> {
     context: Context,
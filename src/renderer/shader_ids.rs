@@ -5,6 +5,7 @@
 //! The allocation of internal use IDs should be considered unstable.
 //!
 
+use crate::renderer::light::ShadowFilter;
 use crate::texture::{ColorTexture, DepthTexture};
 
 use open_enum::open_enum;
@@ -31,6 +32,7 @@ macro_rules! bitfield_bit {
 ///
 /// Generates a function accepting bit parameters, ordering from least significant bit to most significant bit
 /// bitfield_bit abstracts out the process of converting a tuple of bools into a single int literal
+/// Optionally, the bits can be followed by a value which self-describes its bits and is placed after the bits
 ///
 macro_rules! enum_bitfield {
     ($base_name:ident, $name:ident($($field:ident),+ $(,)?)) => {
@@ -43,6 +45,18 @@ macro_rules! enum_bitfield {
             )
         }
     };
+
+    ($base_name:ident, $name:ident($($field:ident),+ ; $value:ident: $valueType:ty)) => {
+        #[allow(non_snake_case)]
+        #[inline]
+        pub(crate) fn $name($($field: bool),+, $value: $valueType) -> Self {
+            Self(
+                Self::$base_name.0
+                    | bitfield_bit!($($field),+ << 0)
+                    | $value.id() << [$(stringify!($field)),+].len()
+            )
+        }
+    };
 }
 
 ///
@@ -97,20 +111,28 @@ pub enum GeometryId {
     TerrainPatch = 0x8002,
     Sprites = 0x8004,
     WaterPatch = 0x8005,
-    MeshBase = 0x8010,           // To 0x801F
+    MeshBase = 0x8020,           // To 0x803F
     ParticleSystemBase = 0x8040, // To 0x807F
     InstancedMeshBase = 0x8080,  // To 0x80FF
 }
 
 impl GeometryId {
-    enum_bitfield!(MeshBase, Mesh(normal, tangents, uv, color));
+    enum_bitfield!(MeshBase, Mesh(normal, tangents, uv, color, uv2));
     enum_bitfield!(
         ParticleSystemBase,
         ParticleSystem(normal, tangents, uv, color, instance_color, instance_uv)
     );
     enum_bitfield!(
         InstancedMeshBase,
-        InstancedMesh(normal, tangents, uv, color, instance_color, instance_uv)
+        InstancedMesh(
+            normal,
+            tangents,
+            uv,
+            color,
+            instance_color,
+            instance_uv,
+            uv2
+        )
     );
 }
 
@@ -122,40 +144,78 @@ impl GeometryId {
 #[open_enum]
 #[repr(u16)]
 pub enum EffectMaterialId {
-    LightingPassEffectBase = 0x5000, // To 0x503F
+    LightingPassEffectBase = 0x5000, // To 0x547F (has holes)
     WaterEffectBase = 0x5800,        // To 0x583F
     CopyEffectBase = 0x6000,         // To 0x603F
     ScreenEffectBase = 0x6800,       // To 0x683F
     FogEffectBase = 0x7000,          // To 0x703F
     FxaaEffectBase = 0x7800,         // To 0x7838 (has holes)
 
-    ColorMaterialBase = 0x8000, // To 0x8001
-    DepthMaterial = 0x8002,
     PositionMaterial = 0x8003,
     SkyboxMaterial = 0x8004,
     UVMaterial = 0x8005,
-    NormalMaterialBase = 0x8006, // To 0x8007
+    WireframeMaterial = 0x8006,
     IntersectionMaterial = 0x800B,
     IsosurfaceMaterial = 0x800C,
     ImpostersMaterial = 0x800D,
     BrdfMaterial = 0x800E,
     IrradianceMaterial = 0x800F,
-    ORMMaterialBase = 0x8010,              // To 0x8013
-    PhysicalMaterialBase = 0x8020,         // To 0x803F
-    DeferredPhysicalMaterialBase = 0x8040, // To 0x807F
+    ORMMaterialBase = 0x8010, // To 0x8013
     PrefilterMaterial = 0x8080,
+    EnvironmentSideMaterial = 0x8081,
+    TransmittanceMaterialBase = 0x8084,     // To 0x8087
+    DepthMaterialBase = 0x8088,             // To 0x808B
+    ColorMaterialBase = 0x808C,             // To 0x808F
+    NormalMaterialBase = 0x8090,            // To 0x8093
+    ToonMaterialBase = 0x8094,              // To 0x8097
+    MatcapMaterialBase = 0x8098,            // To 0x809B
+    VolumeMaterialBase = 0x809C,            // To 0x809F
+    TriplanarPhysicalMaterialBase = 0x80A0, // To 0x80AF
+    TerrainMaterialBase = 0x80B0,           // To 0x80B7
+    DeferredPhysicalMaterialBase = 0x8100,  // To 0x817F
+    PhysicalMaterialBase = 0xA000,          // To 0xBFFF
+
+    OrderIndependentTransparencyEffectBase = 0xC000, // To 0xC0FF
 }
 
 impl EffectMaterialId {
-    enum_effectfield!(LightingPassEffectBase, LightingPassEffect(...Default));
+    #[allow(non_snake_case)]
+    #[inline]
+    pub(crate) fn LightingPassEffect(
+        color_texture: ColorTexture,
+        depth_texture: DepthTexture,
+        extensions: bool,
+    ) -> Self {
+        Self(
+            Self::LightingPassEffectBase.0
+                | color_texture.id()
+                | depth_texture.id()
+                | if extensions { 0x400 } else { 0 },
+        )
+    }
     enum_effectfield!(WaterEffectBase, WaterEffect(...Default));
     enum_effectfield!(CopyEffectBase, CopyEffect(Option<...Default>));
     enum_effectfield!(ScreenEffectBase, ScreenEffect(Option<...Default>));
     enum_effectfield!(FogEffectBase, FogEffect(...Default));
     enum_effectfield!(FxaaEffectBase, FxaaEffect(color_texture: ColorTexture));
+    enum_effectfield!(
+        OrderIndependentTransparencyEffectBase,
+        OrderIndependentTransparencyEffect(color_texture: ColorTexture)
+    );
 
-    enum_bitfield!(ColorMaterialBase, ColorMaterial(texture));
-    enum_bitfield!(NormalMaterialBase, NormalMaterial(normal_texture));
+    enum_bitfield!(ColorMaterialBase, ColorMaterial(texture, alpha_cutout));
+    enum_bitfield!(
+        DepthMaterialBase,
+        DepthMaterial(alpha_cutout, alpha_texture)
+    );
+    enum_bitfield!(
+        NormalMaterialBase,
+        NormalMaterial(normal_texture, double_sided)
+    );
+    enum_bitfield!(
+        MatcapMaterialBase,
+        MatcapMaterial(normal_texture, double_sided)
+    );
     enum_bitfield!(
         ORMMaterialBase,
         ORMMaterial(metallic_roughness_texture, occlusion_texture)
@@ -168,6 +228,14 @@ impl EffectMaterialId {
             occlusion_texture,
             normal_texture,
             emissive_texture,
+            clearcoat_normal_texture,
+            transmission,
+            transmission_texture,
+            alpha_cutout,
+            double_sided,
+            height_texture,
+            lightmap_texture,
+            wireframe,
         )
     );
     enum_bitfield!(
@@ -179,8 +247,31 @@ impl EffectMaterialId {
             normal_texture,
             emissive_texture,
             alpha_cutout,
+            double_sided,
         )
     );
+    enum_bitfield!(ToonMaterialBase, ToonMaterial(albedo_texture, ramp));
+    enum_bitfield!(
+        VolumeMaterialBase,
+        VolumeMaterial(shading, maximum_intensity_projection)
+    );
+    enum_bitfield!(
+        TriplanarPhysicalMaterialBase,
+        TriplanarPhysicalMaterial(
+            albedo_texture,
+            metallic_roughness_texture,
+            occlusion_texture,
+            normal_texture,
+        )
+    );
+    enum_bitfield!(
+        TerrainMaterialBase,
+        TerrainMaterial(normal_textures, splat_maps, triplanar)
+    );
+    enum_bitfield!(
+        TransmittanceMaterialBase,
+        TransmittanceMaterial(texture, alpha_cutout)
+    );
 }
 
 ///
@@ -191,14 +282,40 @@ impl EffectMaterialId {
 #[open_enum]
 #[repr(u8)]
 pub enum LightId {
-    AmbientLightBase = 0x80,     // To 0x81
-    DirectionalLightBase = 0x82, // To 0x83
-    PointLight = 0x84,
-    SpotLightBase = 0x86, // To 0x87
+    AmbientLightBase = 0x80, // To 0x81
+    RectangleLight = 0x82,
+    DiskLight = 0x83,
+    PointLightBase = 0x84, // To 0x85
+    BufferedLights = 0x86,
+    ClusteredLights = 0x87,
+    HemisphereLight = 0x88,
+    SphericalHarmonicsAmbientLightBase = 0x8A, // To 0x8B
+    ReflectionProbesBase = 0x90,               // To 0x94
+    SpotLightBase = 0xA0,                      // To 0xBF
+    DirectionalLightBase = 0xC0,               // To 0xDF
+    // Not a light, appended to the lights of the programs used by render_order_independent
+    OrderIndependentTransparency = 0xFF,
 }
 
 impl LightId {
     enum_bitfield!(AmbientLightBase, AmbientLight(environment));
-    enum_bitfield!(DirectionalLightBase, DirectionalLight(shadow_texture));
-    enum_bitfield!(SpotLightBase, SpotLight(shadow_texture));
+    enum_bitfield!(
+        SphericalHarmonicsAmbientLightBase,
+        SphericalHarmonicsAmbientLight(environment)
+    );
+    enum_bitfield!(
+        DirectionalLightBase,
+        DirectionalLight(shadow_texture, cascaded_shadow_texture, transmittance_texture; shadow_filter: ShadowFilter)
+    );
+    enum_bitfield!(PointLightBase, PointLight(shadow_texture));
+
+    #[allow(non_snake_case)]
+    #[inline]
+    pub(crate) fn ReflectionProbes(probe_count: u8) -> Self {
+        Self(Self::ReflectionProbesBase.0 | probe_count)
+    }
+    enum_bitfield!(
+        SpotLightBase,
+        SpotLight(shadow_texture, photometric_profile, transmittance_texture; shadow_filter: ShadowFilter)
+    );
 }